@@ -9,10 +9,131 @@ mod test {
         let cargo_dir: &Path = cargo_dir.as_ref();
         let cargo_dir = cargo_dir.join("test_files/src/file.rs");
 
-        rcss_bundler::process_styles("test_files", style_collector.clone(), cargo_dir.as_ref());
+        rcss_bundler::process_styles(
+            "test_files",
+            style_collector.clone(),
+            cargo_dir.as_ref(),
+            None,
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap();
         let output = style_collector.borrow().to_styles();
         let output = output.join("");
 
-        assert_eq!(output, ".my-class2-XUSD{color:#00f}.my-class-Mlfe{color:red}.container-PTCU{background-color:#000}")
+        assert_eq!(output, ".my-class2-9vrb{color:#00f}.my-class-StkI{color:red}.container-HawM{background-color:#000}")
+    }
+
+    #[test]
+    fn test_invalid_targets() {
+        let style_collector = Rc::new(RefCell::new(rcss_bundler::full::Collector::new()));
+        let cargo_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| "./".to_string());
+        let cargo_dir: &Path = cargo_dir.as_ref();
+        let file = cargo_dir.join("test_files/src/file.rs");
+
+        let err = rcss_bundler::process_styles(
+            "test_files",
+            style_collector,
+            file.as_ref(),
+            Some("not a browser"),
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("`not a browser`"));
+    }
+
+    #[test]
+    fn test_invalid_style() {
+        let style_collector = Rc::new(RefCell::new(rcss_bundler::full::Collector::new()));
+        let cargo_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| "./".to_string());
+        let cargo_dir: &Path = cargo_dir.as_ref();
+        let file = cargo_dir.join("test_files/src/invalid.rs");
+
+        let err = rcss_bundler::process_styles(
+            "test_files",
+            style_collector,
+            file.as_ref(),
+            None,
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            rcss_bundler::full::Error::InvalidStyle { ref file, .. } if file.ends_with("invalid.rs")
+        ));
+    }
+
+    #[test]
+    fn test_minified_names_of_modules() {
+        let style_collector = Rc::new(RefCell::new(rcss_bundler::full::Collector::new()));
+        let cargo_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| "./".to_string());
+        let cargo_dir: &Path = cargo_dir.as_ref();
+        let file = cargo_dir.join("test_files/src/names.rs");
+
+        let naming = rcss_core::NamingConfig {
+            strategy: rcss_core::NamingStrategy::Minified,
+            ..Default::default()
+        };
+        rcss_bundler::process_styles(
+            "test_files",
+            style_collector.clone(),
+            file.as_ref(),
+            None,
+            naming,
+            Default::default(),
+        )
+        .unwrap();
+        let output_path = cargo_dir.join("target/rcss_test_names.css");
+        std::fs::create_dir_all(output_path.parent().unwrap()).unwrap();
+        let options = rcss_bundler::BundleOption {
+            output_path: output_path.display().to_string(),
+            minify: true,
+            watch_mode: rcss_bundler::WatchMode::AllPackages,
+            targets: None,
+            source_map: false,
+        };
+        style_collector.borrow().save_names_with(&options);
+        let names =
+            std::fs::read_to_string(cargo_dir.join("target/rcss_test_names.names.json")).unwrap();
+        // Structs with the same name in different modules have different keys.
+        assert!(names.contains("\"test_files__names_first__Button__primary\""));
+        assert!(names.contains("\"test_files__names_second__Button__primary\""));
+    }
+
+    #[test]
+    fn test_source_map() {
+        let style_collector = Rc::new(RefCell::new(rcss_bundler::full::Collector::new()));
+        let cargo_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| "./".to_string());
+        let cargo_dir: &Path = cargo_dir.as_ref();
+        let file = cargo_dir.join("test_files/src/file.rs");
+
+        rcss_bundler::process_styles(
+            "test_files",
+            style_collector.clone(),
+            file.as_ref(),
+            None,
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap();
+        let output_path = cargo_dir.join("target/rcss_test_source_map.css");
+        std::fs::create_dir_all(output_path.parent().unwrap()).unwrap();
+        let options = rcss_bundler::BundleOption {
+            output_path: output_path.display().to_string(),
+            minify: true,
+            watch_mode: rcss_bundler::WatchMode::AllPackages,
+            targets: None,
+            source_map: true,
+        };
+        let styles = rcss_bundler::full::Styles::from_unsorted(style_collector.borrow().clone());
+        style_collector
+            .borrow()
+            .save_source_map_with(&styles, &options);
+        let map = std::fs::read_to_string(format!("{}.map", options.output_path)).unwrap();
+        // Sources are relative to source map.
+        assert!(map.contains("\"sources\":[\"../test_files/src/file.rs\"]"));
+        assert!(!map.contains("\"mappings\":\"\""));
     }
 }
@@ -0,0 +1 @@
+rcss::css! { @rcss(pub struct Broken); .a..b { color: red; } }
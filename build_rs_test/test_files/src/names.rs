@@ -0,0 +1,2 @@
+mod names_first;
+mod names_second;
@@ -0,0 +1 @@
+rcss::css! { @rcss(pub struct Button); .primary { color: blue; } }
@@ -21,6 +21,8 @@ use proc_macro2::TokenStream;
 
 pub type RcMacro<'a> = Rc<RefCell<dyn FnMut(MacroContext, TokenStream) + 'a>>;
 pub type MacroMap<'a> = BTreeMap<String, RcMacro<'a>>;
+pub type RcConst<'a> = Rc<RefCell<dyn FnMut(MacroContext, &syn::ItemConst) + 'a>>;
+pub type RcUse<'a> = Rc<RefCell<dyn FnMut(MacroContext, &syn::ItemUse) + 'a>>;
 
 /// Provided context to macro handler.
 #[derive(Clone, Default, Debug)]
@@ -32,12 +34,16 @@ pub struct MacroContext {
     pub fn_call_name: Option<String>,
     // Somewhere at module path, one used `#[path = "foo.rs"]`
     pub used_path_attr: bool,
+    /// Path to source file, that is currently visited.
+    pub source_file: PathBuf,
     src_filedir: PathBuf,
     // TODO: linenum/colnum
 }
 #[derive(Clone)]
 pub struct Visitor<'a> {
     searched_imports: MacroMap<'a>,
+    const_handler: Option<RcConst<'a>>,
+    use_handler: Option<RcUse<'a>>,
 
     context: MacroContext,
 }
@@ -58,6 +64,8 @@ impl<'a> Visitor<'a> {
     pub fn new() -> Self {
         Self {
             searched_imports: BTreeMap::new(),
+            const_handler: None,
+            use_handler: None,
             context: MacroContext::default(),
         }
     }
@@ -72,6 +80,17 @@ impl<'a> Visitor<'a> {
             self.searched_imports.insert(import, macro_call.clone());
         }
     }
+    /// Set handler that will be called for each `const` item in project.
+    pub fn add_const_handler(
+        &mut self,
+        const_item: impl FnMut(MacroContext, &syn::ItemConst) + 'a,
+    ) {
+        self.const_handler = Some(Rc::new(RefCell::new(const_item)));
+    }
+    /// Set handler that will be called for each `use` item in project.
+    pub fn add_use_handler(&mut self, use_item: impl FnMut(MacroContext, &syn::ItemUse) + 'a) {
+        self.use_handler = Some(Rc::new(RefCell::new(use_item)));
+    }
     pub fn add_rc_macro(&mut self, imports: Vec<String>, macro_call: RcMacro<'a>) {
         for import in imports {
             self.searched_imports.insert(import, macro_call.clone());
@@ -106,6 +125,7 @@ impl<'a> Visitor<'a> {
         Self {
             context: MacroContext {
                 entrypoint: entrypoint_name.to_string(),
+                source_file: entrypoint.to_path_buf(),
                 src_filedir,
                 ..MacroContext::default()
             },
@@ -209,7 +229,8 @@ impl syn::visit::Visit<'_> for Visitor<'_> {
             mod_visitor.context.mod_path = vec![];
             let mod_path = resolve_module_path(&mod_visitor.context, &mod_name);
 
-            let content = std::fs::read_to_string(mod_path).unwrap();
+            let content = std::fs::read_to_string(&mod_path).unwrap();
+            mod_visitor.context.source_file = mod_path;
             mod_visitor.visit_file_content(&content);
             return;
         }
@@ -220,10 +241,25 @@ impl syn::visit::Visit<'_> for Visitor<'_> {
             mod_path.display()
         );
         // Or process file in case of `mod foo;` item.
-        let content = std::fs::read_to_string(mod_path).unwrap();
+        let content = std::fs::read_to_string(&mod_path).unwrap();
+        mod_visitor.context.source_file = mod_path;
         mod_visitor.visit_file_content(&content)
     }
 
+    fn visit_item_use(&mut self, i: &syn::ItemUse) {
+        if let Some(use_handler) = &self.use_handler {
+            use_handler.borrow_mut()(self.context.clone(), i);
+        }
+        syn::visit::visit_item_use(self, i);
+    }
+
+    fn visit_item_const(&mut self, i: &syn::ItemConst) {
+        if let Some(const_handler) = &self.const_handler {
+            const_handler.borrow_mut()(self.context.clone(), i);
+        }
+        syn::visit::visit_item_const(self, i);
+    }
+
     fn visit_macro(&mut self, i: &syn::Macro) {
         if let Some(macro_impl) = self.get_macro(i.path.clone()) {
             macro_impl.borrow_mut()(self.context.clone(), i.tokens.clone());
@@ -231,6 +267,13 @@ impl syn::visit::Visit<'_> for Visitor<'_> {
     }
 }
 
+/// Returns value of macro input, if it is a single string literal (like `css!(r#"..."#)`).
+pub fn literal_input(tokens: &TokenStream) -> Option<String> {
+    syn::parse2::<syn::LitStr>(tokens.clone())
+        .ok()
+        .map(|literal| literal.value())
+}
+
 // Compare two paths, and return new one, if path was renamed.
 // Expect left path to be flat, and right might be nested.
 pub(crate) fn compare_use_tree(left: syn::UseTree, right: syn::UseTree) -> Vec<String> {
@@ -367,6 +410,28 @@ mod test {
         assert!(found)
     }
 
+    #[test]
+    fn test_literal_input() {
+        let mut inputs = vec![];
+        let mut visitor = super::Visitor::new();
+        let macro_call = |_, tokens: TokenStream| {
+            inputs.push(literal_input(&tokens));
+        };
+        visitor.add_macro(vec!["rcss::css".to_owned()], macro_call);
+        let input = syn::parse_str::<syn::File>(
+            r##"
+            fn test() {
+                rcss::css!(r#".a { content: '}'; }"#);
+                rcss::css! { .a { color: red; } }
+            }
+            "##,
+        )
+        .unwrap();
+        syn::visit::visit_file(&mut visitor, &input);
+        drop(visitor);
+        assert_eq!(inputs, vec![Some(".a { content: '}'; }".to_owned()), None]);
+    }
+
     #[test]
     fn test_macro_inside_fn() {
         let mut found = false;
@@ -424,6 +489,50 @@ mod test {
         drop(visitor);
         assert!(found)
     }
+    #[test]
+    fn test_const_item_in_mod() {
+        let mut found = vec![];
+        let mut visitor = super::Visitor::new();
+        visitor.add_const_handler(|ctx, item| {
+            found.push((ctx.mod_path, item.ident.to_string()));
+        });
+        let input = syn::parse_quote!(
+            const ROOT: &str = "root";
+            mod theme {
+                pub const PRIMARY: &str = "red";
+            }
+        );
+        syn::visit::visit_file(&mut visitor, &input);
+        drop(visitor);
+        assert_eq!(
+            found,
+            vec![
+                (vec![], "ROOT".to_owned()),
+                (vec!["theme".to_owned()], "PRIMARY".to_owned())
+            ]
+        )
+    }
+
+    #[test]
+    fn test_use_item_in_mod() {
+        let mut found = vec![];
+        let mut visitor = super::Visitor::new();
+        visitor.add_use_handler(|ctx, item| {
+            let syn::UseTree::Path(path) = &item.tree else {
+                unreachable!()
+            };
+            found.push((ctx.mod_path, path.ident.to_string()));
+        });
+        let input = syn::parse_quote!(
+            mod button {
+                use super::theme::PRIMARY;
+            }
+        );
+        syn::visit::visit_file(&mut visitor, &input);
+        drop(visitor);
+        assert_eq!(found, vec![(vec!["button".to_owned()], "super".to_owned())])
+    }
+
     //check that import handle name;
     #[test]
     fn test_compare_use_by_name() {
@@ -10,6 +10,36 @@ use rcss_layers::{ScopeId, Style};
 
 type ModId = Vec<String>;
 
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(
+        "Scope id `{0}` is generated for different styles, \
+        try to set `salt-scope-id = true` in `[package.metadata.rcss]`"
+    )]
+    ScopeIdCollision(ScopeId),
+    #[error("Invalid browser targets `{query}`: {source}")]
+    InvalidTargets {
+        query: String,
+        source: rcss_core::Error,
+    },
+    #[error("Cannot resolve `{path}` used in style in `{file}`: {reason}")]
+    UnresolvedConst {
+        path: String,
+        file: String,
+        reason: String,
+    },
+    #[error("Failed to process style in `{file}`: {source}")]
+    InvalidStyle {
+        file: String,
+        source: rcss_core::Error,
+    },
+    #[error("Invalid `[package.metadata.rcss]` of crate `{crate_name}`: {source}")]
+    InvalidMetadata {
+        crate_name: String,
+        source: rcss_core::Error,
+    },
+}
+
 #[derive(Clone, Debug)]
 enum DependencyInfo {
     Computed { extend: ModId },
@@ -20,6 +50,12 @@ enum DependencyInfo {
 pub struct Collector {
     declared_structs: BTreeMap<ModId, (DependencyInfo, ScopeId, Style)>,
     other_css: Vec<(DependencyInfo, ScopeId, Style)>,
+    // Readable names of minified classes, keyframes and custom properties.
+    names: BTreeMap<String, String>,
+    // Style of each scope id, to detect collisions.
+    scopes: BTreeMap<ScopeId, Style>,
+    // Source map of style by its scope id.
+    source_maps: BTreeMap<ScopeId, rcss_core::StyleSourceMap>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -164,14 +200,39 @@ impl Styles {
         styles
     }
 
-    pub fn save_with(&self, config: &crate::BundleOption) -> String {
+    /// Render all styles into single css string.
+    pub fn render(&self) -> String {
+        self.render_with_offsets().0
+    }
+
+    /// Render all styles into single css string,
+    /// and return byte offset of style of each scope id in it.
+    pub fn render_with_offsets(&self) -> (String, Vec<(ScopeId, usize)>) {
         let mut resulted_style = String::new();
+        let mut offsets = Vec::new();
         for (root_scope_id, layers) in self.sorted.styles.iter() {
-            resulted_style.push_str(
-                &layers
-                    .render(false, root_scope_id.clone())
-                    .expect("Failed to render style"),
-            )
+            let (style, layer_offsets) = layers
+                .render_with_offsets(false, root_scope_id.clone())
+                .expect("Failed to render style");
+            let base = resulted_style.len();
+            offsets.extend(
+                layer_offsets
+                    .into_iter()
+                    .map(|(scope_id, offset)| (scope_id, base + offset)),
+            );
+            resulted_style.push_str(&style)
+        }
+        (resulted_style, offsets)
+    }
+
+    pub fn save_with(&self, config: &crate::BundleOption) -> String {
+        let mut resulted_style = self.render();
+        if config.source_map {
+            let map_name = source_map_path(config)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            resulted_style.push_str(&format!("\n/*# sourceMappingURL={map_name} */"));
         }
 
         println!("output: {}", config.output_path);
@@ -183,12 +244,86 @@ impl Styles {
         config.output_path.clone()
     }
 }
+fn source_map_path(config: &crate::BundleOption) -> std::path::PathBuf {
+    format!("{}.map", config.output_path).into()
+}
+
 impl Collector {
     pub fn new() -> Self {
         Self {
             declared_structs: BTreeMap::new(),
             other_css: Vec::new(),
+            names: BTreeMap::new(),
+            scopes: BTreeMap::new(),
+            source_maps: BTreeMap::new(),
+        }
+    }
+
+    /// Remember source map of style, it is placed into combined map by position of style with
+    /// same scope id in output.
+    /// Same scope id means same style, so only first source map is kept.
+    pub fn add_source_map(&mut self, scope_id: ScopeId, source_map: rcss_core::StyleSourceMap) {
+        self.source_maps.entry(scope_id).or_insert(source_map);
+    }
+
+    /// Save combined source map of rendered `styles` next to output file (like `styles.css.map`).
+    pub fn save_source_map_with(&self, styles: &Styles, config: &crate::BundleOption) {
+        let (css, offsets) = styles.render_with_offsets();
+        let map_path = source_map_path(config);
+        let root = map_path
+            .parent()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        let mut builder = rcss_core::source_map::SourceMapBuilder::new(&root);
+        for (scope_id, offset) in offsets {
+            let Some(source_map) = self.source_maps.get(&scope_id) else {
+                continue;
+            };
+            let line = css[..offset].matches('\n').count() as u32;
+            let line_start = css[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            builder.add(source_map, line, (offset - line_start) as u32);
         }
+        let content = builder.to_json().expect("Failed to generate source map");
+        std::fs::write(map_path, content).expect("Failed to write source map");
+    }
+
+    /// Remember names of style, to save mapping from readable names (`module_path__Struct__class`)
+    /// to minified ones.
+    pub fn add_names(&mut self, module_path: &str, output: &rcss_core::CssOutput) {
+        let scope = output
+            .declare()
+            .map(|s| s.ident.to_string())
+            .unwrap_or_else(|| output.class_name().to_owned());
+        let names = output
+            .classes_map()
+            .iter()
+            .chain(output.ids_map())
+            .chain(output.keyframes_map())
+            .chain(output.custom_properties_map())
+            .chain(
+                rcss_core::NameKind::ALL
+                    .iter()
+                    .filter_map(|kind| output.scoped_names_map(*kind))
+                    .flatten(),
+            );
+        for (name, info) in names {
+            self.names.insert(
+                format!("{module_path}__{scope}__{name}"),
+                info.class_name.clone(),
+            );
+        }
+    }
+
+    /// Save mapping of minified names next to output file (like `styles.names.json`).
+    /// Does nothing if no style uses minified naming strategy.
+    pub fn save_names_with(&self, config: &crate::BundleOption) {
+        if self.names.is_empty() {
+            return;
+        }
+        let path = std::path::Path::new(&config.output_path).with_extension("names.json");
+        let content =
+            serde_json::to_string_pretty(&self.names).expect("Failed to serialize names mapping");
+        std::fs::write(&path, content).expect("Failed to write names mapping");
     }
 
     pub fn add_style(
@@ -197,10 +332,20 @@ impl Collector {
         scope_id: ScopeId,
         style: Style,
         extend: Option<ModId>,
-    ) {
+    ) -> Result<(), Error> {
         println!("Adding style: {:?}", style);
         println!("File id: {:?}", file_id);
         println!("Extend: {:?}", extend);
+        // Same style can be visited multiple times, but different styles should have different ids.
+        match self.scopes.get(&scope_id) {
+            Some(existing) if existing != &style => {
+                return Err(Error::ScopeIdCollision(scope_id));
+            }
+            Some(_) => {}
+            None => {
+                self.scopes.insert(scope_id.clone(), style.clone());
+            }
+        }
         let order = match extend {
             Some(extend) => DependencyInfo::Computed { extend },
             None => DependencyInfo::Calculated {
@@ -214,6 +359,7 @@ impl Collector {
         } else {
             self.other_css.push((order, scope_id, style.into()));
         }
+        Ok(())
     }
 
     pub fn to_styles(&self) -> Vec<Style> {
@@ -225,3 +371,25 @@ impl Collector {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scope_id_collision() {
+        let mut collector = Collector::new();
+        let file_id = Some(vec!["my_crate".to_string(), "Foo".to_string()]);
+        collector
+            .add_style(file_id.clone(), "_abc".into(), ".a-abc{}".into(), None)
+            .unwrap();
+        // Same style visited twice is fine.
+        collector
+            .add_style(file_id, "_abc".into(), ".a-abc{}".into(), None)
+            .unwrap();
+        let err = collector
+            .add_style(None, "_abc".into(), ".b-abc{}".into(), None)
+            .unwrap_err();
+        assert!(matches!(err, Error::ScopeIdCollision(id) if id == "_abc"));
+    }
+}
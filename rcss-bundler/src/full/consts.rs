@@ -0,0 +1,495 @@
+//! Resolution of rust constants that are interpolated into styles (like `${crate::theme::PRIMARY}`).
+//!
+//! Macro concatenates constants with style at compile time, but bundler can't evaluate rust code,
+//! so it reads const definitions from source, and supports only literal values
+//! (strings, integers, chars, bools) or paths to other constants.
+//! Paths can be relative, start with `crate`, `self` or `super`, or use names imported with `use`,
+//! but only constants of the same crate can be resolved.
+//! Mixins exported from styles (`@rcss(mixin name)`) are registered as constants of their struct.
+
+use std::collections::BTreeMap;
+
+type ConstId = Vec<String>;
+
+// Limit of nested paths and imports, protects from cycles in definitions.
+const MAX_DEPTH: u32 = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ResolveError {
+    #[error("`{0}` is not a valid path")]
+    InvalidPath(String),
+    #[error("const `{0}` is not found")]
+    NotFound(String),
+    #[error("const `{0}` is defined in other crate, only consts of the same crate are supported")]
+    OtherCrate(String),
+    #[error("value of const `{0}` should be a literal or path to other const")]
+    UnsupportedValue(String),
+    #[error("const `{0}` is defined recursively")]
+    Recursive(String),
+}
+
+#[derive(Debug)]
+enum ConstValue {
+    Expr(Box<syn::Expr>),
+    // Literals and paths to other constants, that are concatenated.
+    Parts(Vec<ConstPart>),
+}
+
+#[derive(Debug)]
+enum ConstPart {
+    Literal(String),
+    Path(syn::Path),
+}
+
+#[derive(Default, Debug)]
+pub struct ConstRegistry {
+    // Value of const and mod path, where it was defined (to resolve relative paths in value).
+    consts: BTreeMap<ConstId, (Vec<String>, ConstValue)>,
+    // Names imported with `use` (mod path and name), and mod path of `use` with imported path.
+    imports: BTreeMap<ConstId, (Vec<String>, syn::Path)>,
+    // Paths of modules imported with glob (`use theme::*`) into each module.
+    globs: BTreeMap<Vec<String>, Vec<syn::Path>>,
+}
+
+impl ConstRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_const(&mut self, crate_name: &str, mod_path: &[String], item: &syn::ItemConst) {
+        let mod_path = crate_mod_path(crate_name, mod_path);
+        let mut id = mod_path.clone();
+        id.push(item.ident.to_string());
+        self.consts
+            .insert(id, (mod_path, ConstValue::Expr(item.expr.clone())));
+    }
+
+    /// Register names imported by `use` item, so constants can be referenced by them.
+    pub fn add_use(&mut self, crate_name: &str, mod_path: &[String], item: &syn::ItemUse) {
+        let mod_path = crate_mod_path(crate_name, mod_path);
+        let leading_colon = item.leading_colon;
+        let mut stack = vec![(vec![], &item.tree)];
+        while let Some((mut prefix, tree)) = stack.pop() {
+            let to_path = |segments: Vec<syn::Ident>| syn::Path {
+                leading_colon,
+                segments: segments.into_iter().map(syn::PathSegment::from).collect(),
+            };
+            let (name, path) = match tree {
+                syn::UseTree::Path(path) => {
+                    prefix.push(path.ident.clone());
+                    stack.push((prefix, &path.tree));
+                    continue;
+                }
+                syn::UseTree::Group(group) => {
+                    stack.extend(group.items.iter().map(|tree| (prefix.clone(), tree)));
+                    continue;
+                }
+                syn::UseTree::Glob(_) => {
+                    self.globs
+                        .entry(mod_path.clone())
+                        .or_default()
+                        .push(to_path(prefix));
+                    continue;
+                }
+                // `use theme::{self}` imports module itself.
+                syn::UseTree::Name(name) if name.ident == "self" => {
+                    let Some(name) = prefix.last().cloned() else {
+                        continue;
+                    };
+                    (name, prefix)
+                }
+                syn::UseTree::Name(name) => {
+                    prefix.push(name.ident.clone());
+                    (name.ident.clone(), prefix)
+                }
+                syn::UseTree::Rename(rename) if rename.rename == "_" => continue,
+                syn::UseTree::Rename(rename) => {
+                    if rename.ident != "self" {
+                        prefix.push(rename.ident.clone());
+                    }
+                    (rename.rename.clone(), prefix)
+                }
+            };
+            let mut id = mod_path.clone();
+            id.push(name.to_string());
+            self.imports.insert(id, (mod_path.clone(), to_path(path)));
+        }
+    }
+
+    /// Register mixin of style, declared as `struct_id` in module `mod_path`.
+    pub fn add_mixin(
+        &mut self,
+        crate_name: &str,
+        mod_path: &[String],
+        struct_id: &[String],
+        name: &str,
+        parts: &[rcss_core::StylePart],
+    ) {
+        let parts = parts
+            .iter()
+            .map(|part| match part {
+                rcss_core::StylePart::Literal(s) => ConstPart::Literal(s.to_string()),
+                rcss_core::StylePart::Const(var) => ConstPart::Path(
+                    syn::parse_str(&var.path).expect("Path of constant should be valid"),
+                ),
+            })
+            .collect();
+        let mut id = struct_id.to_vec();
+        id.push(name.to_owned());
+        self.consts.insert(
+            id,
+            (
+                crate_mod_path(crate_name, mod_path),
+                ConstValue::Parts(parts),
+            ),
+        );
+    }
+
+    /// Resolve value of constant, by path used in module `mod_path`.
+    pub fn resolve(
+        &self,
+        crate_name: &str,
+        mod_path: &[String],
+        path: &str,
+    ) -> Result<String, ResolveError> {
+        let path = syn::parse_str::<syn::Path>(path)
+            .map_err(|_| ResolveError::InvalidPath(path.to_owned()))?;
+        let id = self.resolve_path(crate_name, &crate_mod_path(crate_name, mod_path), &path, 0)?;
+        self.resolve_id(crate_name, &id, 0)
+    }
+
+    // Returns id of constant, that is referenced by path in module `mod_path`.
+    fn resolve_path(
+        &self,
+        crate_name: &str,
+        mod_path: &[String],
+        path: &syn::Path,
+        depth: u32,
+    ) -> Result<ConstId, ResolveError> {
+        let id = normalize_path(crate_name, mod_path, path);
+        self.resolve_imports(crate_name, id, depth)
+    }
+
+    // Replace imported names in absolute path, with paths they are imported from.
+    fn resolve_imports(
+        &self,
+        crate_name: &str,
+        id: ConstId,
+        depth: u32,
+    ) -> Result<ConstId, ResolveError> {
+        if depth > MAX_DEPTH {
+            return Err(ResolveError::Recursive(id.join("::")));
+        }
+        if self.consts.contains_key(&id) {
+            return Ok(id);
+        }
+        for len in 1..=id.len() {
+            if let Some((use_mod_path, path)) = self.imports.get(&id[..len]) {
+                let mut imported = normalize_path(crate_name, use_mod_path, path);
+                imported.extend_from_slice(&id[len..]);
+                let result = self.resolve_imports(crate_name, imported, depth + 1);
+                // Path in `use` can also start with name of other crate (like `use other_crate::COLOR`).
+                if matches!(result, Err(ResolveError::NotFound(_))) && is_relative(path) {
+                    let mut imported = normalize_path(crate_name, &[], path);
+                    imported.extend_from_slice(&id[len..]);
+                    return self.resolve_imports(crate_name, imported, depth + 1);
+                }
+                return result;
+            }
+        }
+        let (name, module) = id.split_last().expect("Path should not be empty");
+        for glob in self.globs.get(module).into_iter().flatten() {
+            let mut imported = normalize_path(crate_name, module, glob);
+            imported.push(name.clone());
+            if let Ok(id) = self.resolve_imports(crate_name, imported, depth + 1) {
+                return Ok(id);
+            }
+        }
+        if id[0] != crate_name {
+            return Err(ResolveError::OtherCrate(id.join("::")));
+        }
+        Err(ResolveError::NotFound(id.join("::")))
+    }
+
+    fn resolve_id(
+        &self,
+        crate_name: &str,
+        id: &ConstId,
+        depth: u32,
+    ) -> Result<String, ResolveError> {
+        if depth > MAX_DEPTH {
+            return Err(ResolveError::Recursive(id.join("::")));
+        }
+        let (mod_path, value) = self
+            .consts
+            .get(id)
+            .ok_or_else(|| ResolveError::NotFound(id.join("::")))?;
+        let resolve_path = |path| {
+            let id = self.resolve_path(crate_name, mod_path, path, depth + 1)?;
+            self.resolve_id(crate_name, &id, depth + 1)
+        };
+        let expr = match value {
+            ConstValue::Expr(expr) => &**expr,
+            ConstValue::Parts(parts) => {
+                return parts
+                    .iter()
+                    .map(|part| match part {
+                        ConstPart::Literal(s) => Ok(s.clone()),
+                        ConstPart::Path(path) => resolve_path(path),
+                    })
+                    .collect();
+            }
+        };
+        let value = match expr {
+            syn::Expr::Lit(syn::ExprLit { lit, .. }) => lit_value(lit, false),
+            syn::Expr::Unary(syn::ExprUnary {
+                op: syn::UnOp::Neg(_),
+                expr,
+                ..
+            }) => match &**expr {
+                syn::Expr::Lit(syn::ExprLit { lit, .. }) => lit_value(lit, true),
+                _ => None,
+            },
+            syn::Expr::Path(syn::ExprPath { path, .. }) => return resolve_path(path),
+            _ => None,
+        };
+        value.ok_or_else(|| ResolveError::UnsupportedValue(id.join("::")))
+    }
+}
+
+// Make mod path start from crate name.
+fn crate_mod_path(crate_name: &str, mod_path: &[String]) -> Vec<String> {
+    let mut mod_path = mod_path.to_vec();
+    if mod_path.first().map(String::as_str) != Some(crate_name) {
+        mod_path.insert(0, crate_name.to_string());
+    }
+    mod_path
+}
+
+// Returns true if path is relative to current module.
+fn is_relative(path: &syn::Path) -> bool {
+    path.leading_colon.is_none()
+        && !matches!(
+            path.segments[0].ident.to_string().as_str(),
+            "crate" | "self" | "super"
+        )
+}
+
+// Convert path to absolute path, that starts from crate name (or name of other crate).
+fn normalize_path(crate_name: &str, mod_path: &[String], path: &syn::Path) -> ConstId {
+    let mut segments = path
+        .segments
+        .iter()
+        .map(|s| s.ident.to_string())
+        .collect::<Vec<_>>();
+    if path.leading_colon.is_some() {
+        return segments;
+    }
+    match segments[0].as_str() {
+        "crate" => segments[0] = crate_name.to_string(),
+        "self" => {
+            segments = mod_path
+                .iter()
+                .cloned()
+                .chain(segments.drain(1..))
+                .collect();
+        }
+        "super" => {
+            let supers = segments.iter().take_while(|s| *s == "super").count();
+            // Crate name is kept, even if path points outside of crate.
+            let parent = &mod_path[..mod_path.len().saturating_sub(supers).max(1)];
+            segments = parent
+                .iter()
+                .cloned()
+                .chain(segments.drain(supers..))
+                .collect();
+        }
+        _ => segments = mod_path.iter().cloned().chain(segments).collect(),
+    }
+    segments
+}
+
+// Format literal the same way as `const_format::concatcp` does.
+fn lit_value(lit: &syn::Lit, negative: bool) -> Option<String> {
+    let sign = if negative { "-" } else { "" };
+    match lit {
+        syn::Lit::Str(s) if !negative => Some(s.value()),
+        syn::Lit::Char(c) if !negative => Some(c.value().to_string()),
+        syn::Lit::Bool(b) if !negative => Some(b.value.to_string()),
+        syn::Lit::Int(i) => Some(format!("{sign}{}", i.base10_digits())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolve_consts() {
+        let mut registry = ConstRegistry::new();
+        let items: Vec<(Vec<String>, syn::ItemConst)> = vec![
+            (
+                vec![],
+                syn::parse_quote!(
+                    const ROOT: &str = "root";
+                ),
+            ),
+            (
+                vec!["theme".into()],
+                syn::parse_quote!(
+                    pub const PRIMARY: &str = "red";
+                ),
+            ),
+            (
+                vec!["theme".into()],
+                syn::parse_quote!(
+                    pub const ALIAS: &str = PRIMARY;
+                ),
+            ),
+            (
+                vec!["theme".into()],
+                syn::parse_quote!(
+                    pub const OFFSET: i32 = -4;
+                ),
+            ),
+        ];
+        for (mod_path, item) in &items {
+            registry.add_const("my_crate", mod_path, item);
+        }
+        let root = vec!["my_crate".to_string()];
+        assert_eq!(registry.resolve("my_crate", &[], "ROOT").unwrap(), "root");
+        assert_eq!(
+            registry
+                .resolve("my_crate", &root, "crate::theme::PRIMARY")
+                .unwrap(),
+            "red"
+        );
+        assert_eq!(
+            registry.resolve("my_crate", &[], "theme::ALIAS").unwrap(),
+            "red"
+        );
+        assert_eq!(
+            registry
+                .resolve("my_crate", &[], "::my_crate::theme::OFFSET")
+                .unwrap(),
+            "-4"
+        );
+        assert!(matches!(
+            registry.resolve("my_crate", &[], "theme::MISSING"),
+            Err(ResolveError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_imports() {
+        let mut registry = ConstRegistry::new();
+        let consts: Vec<(Vec<String>, syn::ItemConst)> = vec![
+            (
+                vec!["theme".into()],
+                syn::parse_quote!(
+                    pub const PRIMARY: &str = "red";
+                ),
+            ),
+            (
+                vec!["theme".into(), "dark".into()],
+                syn::parse_quote!(
+                    pub const BACKGROUND: &str = "black";
+                ),
+            ),
+            (
+                vec!["theme".into(), "dark".into()],
+                syn::parse_quote!(
+                    pub const ACCENT: &str = super::PRIMARY;
+                ),
+            ),
+        ];
+        for (mod_path, item) in &consts {
+            registry.add_const("my_crate", mod_path, item);
+        }
+        let uses: Vec<(Vec<String>, syn::ItemUse)> = vec![
+            (
+                vec!["button".into()],
+                syn::parse_quote!(
+                    use crate::theme::{self as colors, dark::BACKGROUND as BG};
+                ),
+            ),
+            (
+                vec!["button".into(), "inner".into()],
+                syn::parse_quote!(
+                    use super::super::theme::dark::*;
+                ),
+            ),
+            (
+                vec!["button".into()],
+                syn::parse_quote!(
+                    use other_crate::COLOR;
+                ),
+            ),
+        ];
+        for (mod_path, item) in &uses {
+            registry.add_use("my_crate", mod_path, item);
+        }
+        let button = ["button".to_string()];
+        let inner = ["button".to_string(), "inner".to_string()];
+        assert_eq!(
+            registry.resolve("my_crate", &button, "BG").unwrap(),
+            "black"
+        );
+        assert_eq!(
+            registry
+                .resolve("my_crate", &button, "colors::PRIMARY")
+                .unwrap(),
+            "red"
+        );
+        assert_eq!(
+            registry
+                .resolve("my_crate", &button, "super::theme::dark::ACCENT")
+                .unwrap(),
+            "red"
+        );
+        assert_eq!(
+            registry.resolve("my_crate", &inner, "ACCENT").unwrap(),
+            "red"
+        );
+        assert!(matches!(
+            registry.resolve("my_crate", &button, "COLOR"),
+            Err(ResolveError::OtherCrate(path)) if path == "other_crate::COLOR"
+        ));
+    }
+
+    #[test]
+    fn resolve_mixins() {
+        let mut registry = ConstRegistry::new();
+        let item: syn::ItemConst = syn::parse_quote!(
+            pub const ACCENT: &str = "blue";
+        );
+        registry.add_const("my_crate", &["theme".into()], &item);
+        let output = rcss_core::CssProcessor::process_style(
+            r#"
+            @rcss(pub struct Base);
+            @rcss(mixin focus) { outline: 1px solid ${crate::theme::ACCENT}; }
+            "#,
+        )
+        .unwrap();
+        let struct_id = ["my_crate".to_string(), "Base".to_string()];
+        let declarations = &output.mixins()["focus"];
+        registry.add_mixin(
+            "my_crate",
+            &[],
+            &struct_id,
+            "focus",
+            &output.split_consts(declarations),
+        );
+        assert_eq!(
+            registry.resolve("my_crate", &[], "Base::focus").unwrap(),
+            "outline:1px solid blue"
+        );
+        assert_eq!(
+            registry
+                .resolve("my_crate", &["theme".into()], "crate::Base::focus")
+                .unwrap(),
+            "outline:1px solid blue"
+        );
+    }
+}
@@ -9,31 +9,107 @@ use std::{
 use syn::spanned::Spanned;
 
 use macro_visit::Visitor;
-use rcss_core::CssOutput;
+use rcss_core::{
+    CssOutput, CssProcessorOptions, NamingConfig, NamingStrategy, ScopingMode, SourceLocation,
+};
 
 mod collect_styles;
+mod consts;
 pub use collect_styles::*;
 // Returns (StructName, PathToExtend)
-fn preprocess(style: &str) -> Option<CssOutput> {
-    rcss_core::CssProcessor::process_style(style).ok()
+fn preprocess(style: &str, options: &CssProcessorOptions) -> rcss_core::Result<CssOutput> {
+    rcss_core::CssProcessor::process_style_with_options(style, options)
 }
 
 // Scan project_path using syn folder, and find all css macro calls.
+//
+// `targets` is browserslist query, that should be the same as one used by `rcss-macro`.
+// `naming` is naming config of crate, that should be the same as one used by `rcss-macro`.
+// `scoping` is scoping mode of crate, same as `naming`.
+//
+// Returns error if `targets` is not a valid query, or if styles can't be collected.
 pub fn process_styles(
     crate_name: &str,
     style_collector: Rc<RefCell<collect_styles::Collector>>,
     entrypoint: &Path,
-) {
+    targets: Option<&str>,
+    naming: NamingConfig,
+    scoping: ScopingMode,
+) -> Result<(), Error> {
     let rcss_name = std::env::var("CARGO_CRATE_NAME").unwrap_or("rcss".to_string());
+    let browsers = match targets {
+        Some(query) => {
+            rcss_core::browsers_from_query(query).map_err(|source| Error::InvalidTargets {
+                query: query.to_owned(),
+                source,
+            })?
+        }
+        None => None,
+    };
 
+    // Styles can use constants that are defined later in source,
+    // so styles are registered only after whole project is visited.
+    let const_registry = RefCell::new(consts::ConstRegistry::new());
+    let outputs = RefCell::new(vec![]);
+
+    let const_handler = |ctx: macro_visit::MacroContext, item: &syn::ItemConst| {
+        const_registry
+            .borrow_mut()
+            .add_const(crate_name, &ctx.mod_path, item);
+    };
+    let use_handler = |ctx: macro_visit::MacroContext, item: &syn::ItemUse| {
+        const_registry
+            .borrow_mut()
+            .add_use(crate_name, &ctx.mod_path, item);
+    };
+
+    let style_error = RefCell::new(None);
     let css_handler = |ctx: macro_visit::MacroContext, token_stream: TokenStream| {
         let style = token_stream
             .span()
             .source_text()
             .expect("cannot find source text for macro call");
 
-        let output = preprocess(&style).expect("Style should be parsable");
+        // Positions in string literal are mapped through its escapes (and opening quote, like `r#"`).
+        let (style, literal) = match macro_visit::literal_input(&token_stream) {
+            Some(value) => (value, Some(style)),
+            None => (style, None),
+        };
+
+        let naming = naming.naming(crate_name, Some(&ctx.source_file));
+        // Line of span is counted from 1.
+        let start = token_stream.span().start();
+        let location = SourceLocation {
+            file: ctx.source_file.display().to_string(),
+            line: start.line.saturating_sub(1) as u32,
+            column: start.column as u32,
+        };
+        // Should be in sync with options used by `rcss-macro`.
+        let mut options = CssProcessorOptions::new()
+            .targets(browsers)
+            .naming(naming.clone())
+            .scoping(scoping)
+            .source_map(location);
+        if let Some(literal) = literal {
+            options = options.literal(literal);
+        }
+        let output = match preprocess(&style, &options) {
+            Ok(output) => output,
+            Err(source) => {
+                // Macro handler can't return error, so only the first one is kept and returned after visit.
+                style_error.borrow_mut().get_or_insert(Error::InvalidStyle {
+                    file: ctx.source_file.display().to_string(),
+                    source,
+                });
+                return;
+            }
+        };
         let struct_name = output.declare().map(|s| s.ident.to_string());
+        if naming.strategy == NamingStrategy::Minified {
+            style_collector
+                .borrow_mut()
+                .add_names(&naming.module_path, &output);
+        }
         let extend = output.extend().map(|s| {
             s.segments
                 .iter()
@@ -72,19 +148,86 @@ pub fn process_styles(
             path
         });
 
-        style_collector.borrow_mut().add_style(
+        outputs.borrow_mut().push((
+            ctx.mod_path,
+            ctx.source_file,
+            output,
             global_struct_id,
-            output.class_name().to_string().into(),
-            output.style_string().into(),
             extend,
-        );
+        ));
     };
     let mut visitor = Visitor::new();
 
     let css_struct_paths = vec![format!("{rcss_name}::css")];
     visitor.add_macro(css_struct_paths, css_handler);
+    visitor.add_const_handler(const_handler);
+    visitor.add_use_handler(use_handler);
 
     visitor.visit_project(entrypoint);
+    drop(visitor);
+    if let Some(error) = style_error.into_inner() {
+        return Err(error);
+    }
+
+    let mut const_registry = const_registry.into_inner();
+    let outputs = outputs.into_inner();
+    // Mixins can be included before their declaration, so register all of them first.
+    for (mod_path, _, output, global_struct_id, _) in &outputs {
+        let Some(struct_id) = global_struct_id else {
+            continue;
+        };
+        for (name, declarations) in output.mixins() {
+            const_registry.add_mixin(
+                crate_name,
+                mod_path,
+                struct_id,
+                name,
+                &output.split_consts(declarations),
+            );
+        }
+    }
+    for (mod_path, source_file, output, global_struct_id, extend) in outputs {
+        let mut style = String::new();
+        // Column of each resolved const in processed style, and change of its length.
+        let mut replacements = vec![];
+        let mut column = 0;
+        for part in output.style_parts() {
+            match part {
+                rcss_core::StylePart::Literal(s) => {
+                    style.push_str(s);
+                    column += s.len();
+                }
+                rcss_core::StylePart::Const(var) => {
+                    let value = const_registry
+                        .resolve(crate_name, &mod_path, &var.path)
+                        .map_err(|e| Error::UnresolvedConst {
+                            path: var.path.clone(),
+                            file: source_file.display().to_string(),
+                            reason: e.to_string(),
+                        })?;
+                    replacements.push((
+                        column as u32,
+                        value.len() as i64 - var.placeholder.len() as i64,
+                    ));
+                    column += var.placeholder.len();
+                    style.push_str(&value);
+                }
+            }
+        }
+        if let Some(source_map) = output.source_map() {
+            style_collector.borrow_mut().add_source_map(
+                output.class_name().to_string().into(),
+                source_map.with_replacements(&replacements),
+            );
+        }
+        style_collector.borrow_mut().add_style(
+            global_struct_id,
+            output.class_name().to_string().into(),
+            style.into(),
+            extend,
+        )?;
+    }
+    Ok(())
 }
 
 pub fn get_config_from_metadata(
@@ -102,9 +245,63 @@ pub fn get_config_from_metadata(
     if let Some(minify) = rcss_metadata.get("minify").and_then(|v| v.as_bool()) {
         options.minify = minify;
     }
+    if let Some(source_map) = rcss_metadata.get("source-map").and_then(|v| v.as_bool()) {
+        options.source_map = source_map;
+    }
+    // Targets can be set either as single browserslist query, or as array of queries.
+    match rcss_metadata.get("targets") {
+        Some(serde_json::Value::String(query)) => options.targets = Some(query.clone()),
+        Some(serde_json::Value::Array(queries)) => {
+            let queries = queries
+                .iter()
+                .filter_map(|q| q.as_str())
+                .collect::<Vec<_>>();
+            options.targets = Some(queries.join(", "))
+        }
+        _ => {}
+    }
     Some(options)
 }
 
+/// Reads naming config of crate from `[package.metadata.rcss]`.
+/// Strategy can be set either as string, or as table with separate strategies for debug and release builds.
+/// Should be in sync with `rcss-macro`.
+pub fn naming_from_metadata(metadata: &serde_json::Value) -> rcss_core::Result<NamingConfig> {
+    let naming = metadata.get("rcss").and_then(|m| m.get("naming"));
+    // Bundler runs in build script, where cargo sets profile of build
+    // (`release` for profiles that inherit from release, and `debug` for others).
+    let profile = std::env::var("PROFILE").unwrap_or_else(|_| "debug".to_owned());
+    let strategy = match naming {
+        Some(serde_json::Value::Object(profiles)) => {
+            profiles.get(&profile).and_then(|v| v.as_str())
+        }
+        Some(value) => value.as_str(),
+        None => None,
+    };
+    let strategy = strategy.map(str::parse).transpose()?.unwrap_or_default();
+    let salt_scope_id = metadata
+        .get("rcss")
+        .and_then(|m| m.get("salt-scope-id"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or_default();
+    Ok(NamingConfig {
+        strategy,
+        salt_scope_id,
+    })
+}
+
+/// Reads scoping mode of crate from `[package.metadata.rcss]` (`scoping = "attribute"`).
+/// Should be in sync with `rcss-macro`.
+pub fn scoping_from_metadata(metadata: &serde_json::Value) -> rcss_core::Result<ScopingMode> {
+    let scoping = metadata
+        .get("rcss")
+        .and_then(|m| m.get("scoping"))
+        .and_then(|v| v.as_str())
+        .map(str::parse)
+        .transpose()?;
+    Ok(scoping.unwrap_or_default())
+}
+
 fn get_crate_id_by_manifest_path(
     metadata: &Metadata,
     manifest_path: &Path,
@@ -138,9 +335,15 @@ fn get_package_by_id<'a>(metadata: &'a Metadata, package_id: &PackageId) -> &'a
 
 // Currently bundler will only support lib and bin targets,
 // and dependency can be only lib
-fn extract_crate_info(package: &Package, is_lib: bool) -> CrateInfo {
-    CrateInfo {
+fn extract_crate_info(package: &Package, is_lib: bool) -> Result<CrateInfo, Error> {
+    let invalid_metadata = |source| Error::InvalidMetadata {
+        crate_name: package.name.clone(),
+        source,
+    };
+    Ok(CrateInfo {
         name: package.name.clone(),
+        naming: naming_from_metadata(&package.metadata).map_err(invalid_metadata)?,
+        scoping: scoping_from_metadata(&package.metadata).map_err(invalid_metadata)?,
         manifest_path: package.manifest_path.clone().into(),
         entrypoints: package
             .targets
@@ -154,12 +357,14 @@ fn extract_crate_info(package: &Package, is_lib: bool) -> CrateInfo {
             })
             .map(|t| t.src_path.clone().into())
             .collect(),
-    }
+    })
 }
 // Read manifest file and find all
 // Get list of crates that depend on rcss
 // Returns path to their manifest file
-pub fn get_depend_crate_info_and_options(manifest_path: &Path) -> (Vec<CrateInfo>, BundleOption) {
+pub fn get_depend_crate_info_and_options(
+    manifest_path: &Path,
+) -> Result<(Vec<CrateInfo>, BundleOption), Error> {
     let mut cmd = MetadataCommand::new();
 
     // panic!("manifest: {}", manifest_path.display());
@@ -184,7 +389,7 @@ pub fn get_depend_crate_info_and_options(manifest_path: &Path) -> (Vec<CrateInfo
     let mut results = vec![extract_crate_info(
         get_package_by_id(&metadata, &root_package),
         false,
-    )];
+    )?];
 
     for dep in deps_ids {
         let package = get_package_by_id(&metadata, &dep);
@@ -193,11 +398,11 @@ pub fn get_depend_crate_info_and_options(manifest_path: &Path) -> (Vec<CrateInfo
         }
         let dep_on_rcss = package.dependencies.iter().any(|d| d.name == "rcss");
         if dep_on_rcss {
-            results.push(extract_crate_info(&package, true));
+            results.push(extract_crate_info(&package, true)?);
         }
     }
 
-    return (results, options);
+    Ok((results, options))
 }
 
 pub enum WatchMode {
@@ -208,6 +413,10 @@ pub struct BundleOption {
     pub output_path: String,
     pub minify: bool,
     pub watch_mode: WatchMode,
+    /// Browserslist query, used to lower and prefix styles.
+    pub targets: Option<String>,
+    /// Save source map next to output file (like `styles.css.map`).
+    pub source_map: bool,
 }
 
 impl Default for BundleOption {
@@ -218,6 +427,8 @@ impl Default for BundleOption {
             output_path,
             minify: true,
             watch_mode: WatchMode::AllPackages,
+            targets: None,
+            source_map: false,
         }
     }
 }
@@ -225,12 +436,16 @@ impl Default for BundleOption {
 #[derive(Debug)]
 pub struct CrateInfo {
     pub name: String,
+    pub naming: NamingConfig,
+    pub scoping: ScopingMode,
     pub manifest_path: PathBuf,
     pub entrypoints: Vec<PathBuf>,
 }
 
-pub fn bundle(root_manifest: &Path) -> String {
-    let (crates, options) = get_depend_crate_info_and_options(root_manifest);
+/// Collect styles of all crates that depend on rcss, and save them into single css file.
+/// Returns path to saved file, or error if configuration of some crate is invalid.
+pub fn bundle(root_manifest: &Path) -> Result<String, Error> {
+    let (crates, options) = get_depend_crate_info_and_options(root_manifest)?;
 
     // TODO: Filter deps that not use macro, like (leptos-rcss)
 
@@ -242,11 +457,24 @@ pub fn bundle(root_manifest: &Path) -> String {
         let entrypoints = &crate_info.entrypoints;
         for entrypoint in entrypoints {
             println!("Processing entrypoint: {:?}", entrypoint);
-            process_styles(&crate_info.name, collected_styles.clone(), &entrypoint);
+            process_styles(
+                &crate_info.name,
+                collected_styles.clone(),
+                &entrypoint,
+                options.targets.as_deref(),
+                crate_info.naming,
+                crate_info.scoping,
+            )?;
         }
     }
+    collected_styles.borrow().save_names_with(&options);
     let styles = collect_styles::Styles::from_unsorted(collected_styles.borrow().clone());
-    styles.save_with(&options)
+    if options.source_map {
+        collected_styles
+            .borrow()
+            .save_source_map_with(&styles, &options);
+    }
+    Ok(styles.save_with(&options))
 }
 
 pub fn bundle_build_rs() {
@@ -255,6 +483,6 @@ pub fn bundle_build_rs() {
     println!("cargo:rerun-if-changed=Cargo.toml");
 
     crate::save_root_manifest_path(&path);
-    let file_out = bundle(&path);
+    let file_out = bundle(&path).unwrap_or_else(|e| panic!("{e}"));
     println!("cargo:rerun-if-changed={file_out}");
 }
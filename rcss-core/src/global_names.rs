@@ -0,0 +1,229 @@
+//! Names, that are global in css like classes, and are scoped in the same way:
+//! cascade layers, container names, counters, grid areas and view transition names.
+//!
+//! Only names declared in style are scoped (like `container-name: card` or `counter-reset: item`),
+//! references to other names (like `@container sidebar (..)`) are kept as is.
+
+use std::collections::BTreeMap;
+
+use lightningcss::{
+    properties::{
+        contain::ContainerNameList,
+        custom::{CustomPropertyName, Token, TokenList, TokenOrValue},
+        grid::{GridLine, GridTemplateAreas},
+        Property,
+    },
+    rules::{container::ContainerName, CssRule},
+    selector::{PseudoElement, ViewTransitionPartName},
+    values::{ident::CustomIdent, string::CowArcStr},
+};
+
+/// Kind of scoped name, see module docs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NameKind {
+    /// Name of cascade layer (`@layer base { .. }`), only top level layer is scoped in nested name (`base.reset`).
+    Layer,
+    /// Name of container (`container-name: card`), referenced by `@container card (..)`.
+    Container,
+    /// Name of counter (`counter-reset: item`), referenced by `counter-increment` and `counter(item)`.
+    Counter,
+    /// Name of grid area (`grid-template-areas: "header header"`), referenced by `grid-area: header`.
+    GridArea,
+    /// Name of view transition (`view-transition-name: hero`), referenced by `::view-transition-old(hero)`.
+    ViewTransition,
+}
+
+impl NameKind {
+    pub const ALL: [NameKind; 5] = [
+        NameKind::Layer,
+        NameKind::Container,
+        NameKind::Counter,
+        NameKind::GridArea,
+        NameKind::ViewTransition,
+    ];
+}
+
+/// Scoped names of each kind (original => scoped name).
+pub(crate) type ScopedNames = BTreeMap<NameKind, BTreeMap<String, String>>;
+
+/// Returns scoped name of `kind`, if name is declared in style.
+pub(crate) fn scoped_name(names: &ScopedNames, kind: NameKind, name: &str) -> Option<String> {
+    let names = names.get(&kind)?;
+    if let Some(scoped) = names.get(name) {
+        return Some(scoped.clone());
+    }
+    // Grid area also defines implicit lines `area-start` and `area-end`.
+    if kind == NameKind::GridArea {
+        for suffix in ["-start", "-end"] {
+            if let Some(scoped) = name.strip_suffix(suffix).and_then(|area| names.get(area)) {
+                return Some(format!("{scoped}{suffix}"));
+            }
+        }
+    }
+    None
+}
+
+// Callback receives kind of name, whether it is declaration, and name itself.
+// It returns new name, if name should be replaced.
+type NameCallback<'a> = dyn FnMut(NameKind, bool, &str) -> Option<String> + 'a;
+
+fn visit_ident(ident: &mut CowArcStr<'_>, kind: NameKind, declaration: bool, f: &mut NameCallback) {
+    if let Some(name) = f(kind, declaration, ident) {
+        *ident = name.into();
+    }
+}
+
+fn visit_grid_areas(areas: &mut GridTemplateAreas, f: &mut NameCallback) {
+    let GridTemplateAreas::Areas { areas, .. } = areas else {
+        return;
+    };
+    for area in areas.iter_mut().flatten() {
+        if let Some(name) = f(NameKind::GridArea, true, area) {
+            *area = name;
+        }
+    }
+}
+
+fn visit_grid_line(line: &mut GridLine<'_>, f: &mut NameCallback) {
+    match line {
+        GridLine::Area {
+            name: CustomIdent(name),
+        }
+        | GridLine::Line {
+            name: Some(CustomIdent(name)),
+            ..
+        }
+        | GridLine::Span {
+            name: Some(CustomIdent(name)),
+            ..
+        } => visit_ident(name, NameKind::GridArea, false, f),
+        _ => {}
+    }
+}
+
+// Counters are not parsed by lightningcss, so they are searched in tokens of property.
+fn visit_counters(tokens: &mut TokenList<'_>, declaration: Option<bool>, f: &mut NameCallback) {
+    for token in tokens.0.iter_mut() {
+        match token {
+            // Value of `counter-reset`, `counter-set` or `counter-increment`.
+            TokenOrValue::Token(Token::Ident(name)) => {
+                let Some(declaration) = declaration else {
+                    continue;
+                };
+                if !matches!(&**name, "none" | "list-item") {
+                    visit_ident(name, NameKind::Counter, declaration, f);
+                }
+            }
+            // `counter(name)` or `counters(name, ".")`.
+            TokenOrValue::Function(function)
+                if matches!(&*function.name.0, "counter" | "counters") =>
+            {
+                if let Some(TokenOrValue::Token(Token::Ident(name))) =
+                    function.arguments.0.first_mut()
+                {
+                    visit_ident(name, NameKind::Counter, false, f);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Visit names declared or referenced in property.
+pub(crate) fn visit_property(property: &mut Property<'_>, f: &mut NameCallback) {
+    match property {
+        Property::ContainerName(ContainerNameList::Names(names)) => {
+            for ContainerName(CustomIdent(name)) in names.iter_mut() {
+                visit_ident(name, NameKind::Container, true, f);
+            }
+        }
+        Property::Container(container) => {
+            if let ContainerNameList::Names(names) = &mut container.name {
+                for ContainerName(CustomIdent(name)) in names.iter_mut() {
+                    visit_ident(name, NameKind::Container, true, f);
+                }
+            }
+        }
+        Property::ViewTransitionName(CustomIdent(name))
+            if !matches!(&**name, "none" | "auto" | "match-element") =>
+        {
+            visit_ident(name, NameKind::ViewTransition, true, f);
+        }
+        Property::GridTemplateAreas(areas) => visit_grid_areas(areas, f),
+        Property::GridTemplate(template) => visit_grid_areas(&mut template.areas, f),
+        Property::Grid(grid) => visit_grid_areas(&mut grid.areas, f),
+        Property::GridRowStart(line)
+        | Property::GridRowEnd(line)
+        | Property::GridColumnStart(line)
+        | Property::GridColumnEnd(line) => visit_grid_line(line, f),
+        Property::GridRow(row) => {
+            visit_grid_line(&mut row.start, f);
+            visit_grid_line(&mut row.end, f);
+        }
+        Property::GridColumn(column) => {
+            visit_grid_line(&mut column.start, f);
+            visit_grid_line(&mut column.end, f);
+        }
+        Property::GridArea(area) => {
+            visit_grid_line(&mut area.row_start, f);
+            visit_grid_line(&mut area.column_start, f);
+            visit_grid_line(&mut area.row_end, f);
+            visit_grid_line(&mut area.column_end, f);
+        }
+        Property::Custom(custom) => {
+            let declaration = match &custom.name {
+                CustomPropertyName::Unknown(name) => match &*name.0 {
+                    "counter-reset" => Some(true),
+                    "counter-set" | "counter-increment" => Some(false),
+                    _ => None,
+                },
+                CustomPropertyName::Custom(_) => None,
+            };
+            visit_counters(&mut custom.value, declaration, f);
+        }
+        Property::Unparsed(unparsed) => visit_counters(&mut unparsed.value, None, f),
+        _ => {}
+    }
+}
+
+/// Visit names declared or referenced in prelude of rule (without its children).
+pub(crate) fn visit_rule<R>(rule: &mut CssRule<'_, R>, f: &mut NameCallback) {
+    match rule {
+        CssRule::LayerStatement(statement) => {
+            for name in statement.names.iter_mut() {
+                if let Some(first) = name.0.first_mut() {
+                    visit_ident(first, NameKind::Layer, true, f);
+                }
+            }
+        }
+        CssRule::LayerBlock(block) => {
+            if let Some(first) = block.name.as_mut().and_then(|name| name.0.first_mut()) {
+                visit_ident(first, NameKind::Layer, true, f);
+            }
+        }
+        CssRule::Container(container) => {
+            if let Some(ContainerName(CustomIdent(name))) = &mut container.name {
+                visit_ident(name, NameKind::Container, false, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Visit name referenced in pseudo-element.
+/// Returns true for view transition pseudo-elements, which originate from document root.
+pub(crate) fn visit_pseudo_element(pseudo: &mut PseudoElement<'_>, f: &mut NameCallback) -> bool {
+    match pseudo {
+        PseudoElement::ViewTransition => true,
+        PseudoElement::ViewTransitionGroup { part_name }
+        | PseudoElement::ViewTransitionImagePair { part_name }
+        | PseudoElement::ViewTransitionOld { part_name }
+        | PseudoElement::ViewTransitionNew { part_name } => {
+            if let ViewTransitionPartName::Name(CustomIdent(name)) = part_name {
+                visit_ident(name, NameKind::ViewTransition, false, f);
+            }
+            true
+        }
+        _ => false,
+    }
+}
@@ -1,18 +1,26 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, ops::Range};
+
+use crate::source_map::TextEdits;
 
 /// Interpolation token.
 /// Different tokens are handled differently:
 /// - UnwrapLiteral: just place original string as is, into source css file.
-/// Later can be extended with compile time or runtime expressions.
+/// - RuntimeExpr: rust expression, that is evaluated at runtime,
+///   its value is passed to css through scoped custom property.
+/// - ConstExpr: path to rust constant, that is concatenated with css at compile time.
 
 pub enum Token {
     UnwrapLiteral(String),
+    RuntimeExpr(String),
+    ConstExpr(String),
 }
 
 /// Token with information about its position in source.
 pub struct TokenWithId {
     pub token: Token,
     id: String,
+    // Range of `${..}` in original source.
+    range: Range<usize>,
 }
 
 // Store each token in array, and save
@@ -20,15 +28,135 @@ pub struct Interpolation {
     pub tokens: Vec<TokenWithId>,
 }
 impl Interpolation {
-    pub fn unwrap_literals(&self, source: &str) -> String {
-        let mut result = String::from(source);
+    /// Place literals back into source.
+    /// Runtime expressions are replaced with `var()` of placeholder custom property (see [`runtime_var_placeholder`]).
+    /// Constants are replaced with placeholder identifier (see [`const_placeholder`]).
+    /// Returns resulting source, and edits that map it back to source with `${..}` tokens.
+    pub fn unwrap_literals(&self, source: &str) -> (String, TextEdits) {
+        let mut result = String::new();
+        let mut edits = TextEdits::new();
+        let mut rest = source;
+        let mut runtime_id = 0;
+        let mut const_id = 0;
         for token in &self.tokens {
-            match &token.token {
-                Token::UnwrapLiteral(s) => result = result.replace(&token.id, s),
+            let replacement = match &token.token {
+                Token::UnwrapLiteral(s) => s.clone(),
+                Token::RuntimeExpr(_) => {
+                    runtime_id += 1;
+                    format!("var({})", runtime_var_placeholder(runtime_id - 1))
+                }
+                Token::ConstExpr(_) => {
+                    const_id += 1;
+                    const_placeholder(const_id - 1)
+                }
+            };
+            // Tokens are placed in order of their appearance,
+            // so searching from previous token will not match prefix of other token id.
+            let Some(idx) = rest.find(&token.id) else {
+                continue;
+            };
+            result.push_str(&rest[..idx]);
+            let start = result.len();
+            result.push_str(&replacement);
+            edits.push(token.range.clone(), start..result.len());
+            rest = &rest[idx + token.id.len()..];
+        }
+        result.push_str(rest);
+        (result, edits)
+    }
+    /// Returns source of runtime expressions, in order of their appearance.
+    pub fn runtime_expressions(&self) -> impl Iterator<Item = &str> {
+        self.tokens.iter().filter_map(|token| match &token.token {
+            Token::RuntimeExpr(expr) => Some(expr.as_str()),
+            Token::UnwrapLiteral(_) | Token::ConstExpr(_) => None,
+        })
+    }
+    /// Returns paths of interpolated constants, in order of their appearance.
+    pub fn const_expressions(&self) -> impl Iterator<Item = &str> {
+        self.tokens.iter().filter_map(|token| match &token.token {
+            Token::ConstExpr(path) => Some(path.as_str()),
+            Token::UnwrapLiteral(_) | Token::RuntimeExpr(_) => None,
+        })
+    }
+}
+
+/// Identifier that is used in processed css instead of interpolated constant.
+/// Resulting style is split by this placeholders and concatenated with constants values.
+pub fn const_placeholder(id: usize) -> String {
+    format!("__RCSS__CONST_{id}__")
+}
+
+/// Returns true if expression is a path to constant (like `crate::theme::PRIMARY` or `PRIMARY`).
+/// Constants are recognized by naming convention: last segment should be in SCREAMING_SNAKE_CASE.
+/// Statics are named the same way, so paths to them are treated as constants too.
+pub fn is_const_path(expr: &str) -> bool {
+    let Ok(path) = syn::parse_str::<syn::Path>(expr) else {
+        return false;
+    };
+    let Some(last) = path.segments.last() else {
+        return false;
+    };
+    let ident = last.ident.to_string();
+    last.arguments.is_none()
+        && ident.chars().any(|c| c.is_ascii_uppercase())
+        && ident
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Name of custom property that is used in processed css instead of runtime expression.
+/// It is replaced with scoped name after css is generated.
+pub fn runtime_var_placeholder(id: usize) -> String {
+    format!("--__RCSS__VAR_{id}__")
+}
+
+/// Returns true if css contains placeholder of any runtime expression.
+pub fn contains_runtime_var(css: &str) -> bool {
+    css.contains("--__RCSS__VAR_")
+}
+
+// Find closing bracket of interpolation token, skipping nested groups, string and char literals.
+fn find_closing_bracket(source: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = source.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(idx),
+            '}' => depth -= 1,
+            '"' => {
+                // skip string literal
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '\'' => {
+                // skip char literal (like `'}'` or `'\''`), but not lifetime or label (like `'a`).
+                let mut rest = chars.clone();
+                match rest.next() {
+                    Some((_, '\\')) => {
+                        rest.next();
+                        while let Some((_, c)) = rest.next() {
+                            if c == '\'' {
+                                chars = rest;
+                                break;
+                            }
+                        }
+                    }
+                    Some(_) if matches!(rest.next(), Some((_, '\''))) => chars = rest,
+                    _ => {}
+                }
             }
+            _ => {}
         }
-        result
     }
+    None
 }
 
 /// Find any occurrences of ${} in source string and replace it with TOKEN_ID.
@@ -39,39 +167,44 @@ pub fn handle_interpolate(mut source: &str) -> (Interpolation, Cow<'_, str>) {
 
     let mut result = String::new();
     let mut last_id = 0;
+    // Offset of `source` in original string.
+    let mut offset = 0;
     while let Some(start) = source[..].find("${") {
-        let end = source[start..].find("}").unwrap_or_else(|| {
+        let end = find_closing_bracket(&source[start + 2..]).unwrap_or_else(|| {
             panic!(
                 "No closing bracket found for interpolation token at position {}",
                 start
             )
-        }) + start;
+        }) + start
+            + 2;
 
-        let token = &source[start + 2..end].trim_start();
+        let token = &source[start + 2..end].trim();
         let token_id = format!("__RCSS__TOKEN_{}", last_id);
         last_id += 1;
 
-        if token.starts_with("\"") {
+        let token = if token.starts_with('"') {
             let token = token.trim_matches(|c: char| c.is_whitespace() || c == '"');
-            state.tokens.push(TokenWithId {
-                token: Token::UnwrapLiteral(token.to_string()),
-                id: token_id.clone(),
-            });
+            Token::UnwrapLiteral(token.to_string())
+        } else if is_const_path(token) {
+            Token::ConstExpr(token.to_string())
         } else {
-            panic!(
-                "Only string literals are supported in interpolation \"#{{..}}\", got=\"{}\"",
-                token
-            );
-        }
+            Token::RuntimeExpr(token.to_string())
+        };
+        state.tokens.push(TokenWithId {
+            token,
+            id: token_id.clone(),
+            range: offset + start..offset + end + 1,
+        });
 
         result.push_str(&source[..start]);
         result.push_str(&token_id);
         source = &source[end + 1..];
+        offset += end + 1;
     }
     if state.tokens.is_empty() {
         return (state, Cow::Borrowed(source));
     }
-    result.push_str(&source);
+    result.push_str(source);
     (state, result.into())
 }
 
@@ -87,9 +220,67 @@ mod test {
         assert_eq!(result, "background-color: red; left: __RCSS__TOKEN_0;");
         match &interpolation.tokens[0].token {
             Token::UnwrapLiteral(s) => assert_eq!(s, "0em"),
+            Token::RuntimeExpr(_) | Token::ConstExpr(_) => unreachable!(),
         }
     }
 
+    #[test]
+    fn test_handle_const_expr() {
+        let source = "color: ${crate::theme::PRIMARY}; width: ${SIZE}px; top: ${top};";
+        let (interpolation, result) = handle_interpolate(source);
+        assert_eq!(
+            interpolation.const_expressions().collect::<Vec<_>>(),
+            vec!["crate::theme::PRIMARY", "SIZE"]
+        );
+        assert_eq!(
+            interpolation.runtime_expressions().collect::<Vec<_>>(),
+            vec!["top"]
+        );
+        let (result, _) = interpolation.unwrap_literals(&result);
+        assert_eq!(
+            result,
+            "color: __RCSS__CONST_0__; width: __RCSS__CONST_1__px; top: var(--__RCSS__VAR_0__);"
+        );
+    }
+
+    #[test]
+    fn test_handle_runtime_expr() {
+        let source =
+            "color: ${\"red\"}; left: ${ if wide { \"10px\" } else { \"}\" } }; top: ${top};";
+        let (interpolation, result) = handle_interpolate(source);
+        assert_eq!(
+            result,
+            "color: __RCSS__TOKEN_0; left: __RCSS__TOKEN_1; top: __RCSS__TOKEN_2;"
+        );
+        assert_eq!(
+            interpolation.runtime_expressions().collect::<Vec<_>>(),
+            vec!["if wide { \"10px\" } else { \"}\" }", "top"]
+        );
+        let (result, _) = interpolation.unwrap_literals(&result);
+        assert_eq!(
+            result,
+            "color: red; left: var(--__RCSS__VAR_0__); top: var(--__RCSS__VAR_1__);"
+        );
+    }
+
+    #[test]
+    fn test_handle_char_literal() {
+        let source = "content: ${ if open { '{' } else { '}' } }; quote: ${ sep('\\'', '}') }; top: ${'a: loop { break 'a 1 }};";
+        let (interpolation, result) = handle_interpolate(source);
+        assert_eq!(
+            result,
+            "content: __RCSS__TOKEN_0; quote: __RCSS__TOKEN_1; top: __RCSS__TOKEN_2;"
+        );
+        assert_eq!(
+            interpolation.runtime_expressions().collect::<Vec<_>>(),
+            vec![
+                "if open { '{' } else { '}' }",
+                "sep('\\'', '}')",
+                "'a: loop { break 'a 1 }"
+            ]
+        );
+    }
+
     #[test]
     fn test_handle_unwrap() {
         let source = "background-color: red; left: ${\"0em\"}; color: #${\"ff0000\"};";
@@ -99,7 +290,22 @@ mod test {
             "background-color: red; left: __RCSS__TOKEN_0; color: #__RCSS__TOKEN_1;"
         );
         assert_eq!(interpolation.tokens.len(), 2);
-        let result = interpolation.unwrap_literals(&result);
+        let (result, _) = interpolation.unwrap_literals(&result);
         assert_eq!(result, "background-color: red; left: 0em; color: #ff0000;");
     }
+
+    #[test]
+    fn test_unwrap_edits() {
+        let source = (0..11).map(|_| "${\"a\"}").collect::<Vec<_>>().join(" ") + " ${top}";
+        let (interpolation, result) = handle_interpolate(&source);
+        let (result, edits) = interpolation.unwrap_literals(&result);
+        // `__RCSS__TOKEN_1` is prefix of `__RCSS__TOKEN_10`, but each token is replaced only once.
+        assert_eq!(result, "a a a a a a a a a a a var(--__RCSS__VAR_0__)");
+        let var = result.find("var(").unwrap();
+        assert_eq!(
+            edits.original_offset(var + 4),
+            source.find("${top}").unwrap()
+        );
+        assert_eq!(edits.original_offset(2), "${\"a\"} ".len());
+    }
 }
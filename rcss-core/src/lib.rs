@@ -3,92 +3,421 @@
 // - [ ] Procss preprocessor.
 // - [ ] :deep pseudo-elemenet support
 
-use std::{collections::BTreeMap, io::Write, path::Path};
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::Path,
+    sync::{Arc, RwLock},
+};
 
 use lightningcss::{
-    stylesheet::{ParserOptions, PrinterOptions},
+    stylesheet::{MinifyOptions, ParserFlags, ParserOptions, PrinterOptions},
+    targets::{Features, Targets},
     visitor::Visit,
 };
 use rand::{distributions::Distribution, seq::SliceRandom, Rng, SeedableRng};
 use rcss_at_rule::{RcssAtRuleConfig, RcssAtRuleParser};
+use sha2::{Digest, Sha256};
 
 pub mod rcss_at_rule;
 pub mod visitor;
-pub use visitor::Error;
+pub use global_names::NameKind;
+pub use lightningcss::{error::ErrorLocation, targets::Browsers};
+pub use visitor::{ComposedClass, Error};
+pub mod global_names;
 pub mod interpolate;
+pub mod mixin;
+pub mod naming;
+mod native_scope;
+pub mod options;
+pub use naming::{Naming, NamingConfig, NamingStrategy};
+pub use options::{CssProcessorOptions, Drafts, ScopingMode};
+pub mod source_map;
+pub use source_map::{SourceLocation, StyleSourceMap};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub struct CssProcessor<'i> {
-    style: lightningcss::stylesheet::StyleSheet<'i, 'i, RcssAtRuleConfig>,
+    style: lightningcss::stylesheet::StyleSheet<'i, 'i, RcssAtRuleConfig<'i>>,
     // use array instead of string to avoid heap allocation.
     random_ident: [char; 7],
+    // Invalid rules and declarations, that were skipped with error recovery.
+    warnings: Vec<String>,
 }
 impl<'src> CssProcessor<'src> {
-    // TODO: Handle error
-    fn new(style: &'src str) -> Result<Self> {
-        let this = Self {
-            random_ident: Self::init_random_class(style),
-            style: lightningcss::stylesheet::StyleSheet::parse_with(
-                style,
-                ParserOptions::default(),
-                &mut RcssAtRuleParser,
-            )
-            .map_err(|e| e.into_owned())?,
+    // `paths` are paths of interpolated constants and included mixins, in order of their placeholders.
+    fn new(style: &'src str, options: &CssProcessorOptions, paths: &[&str]) -> Result<Self> {
+        let mut parser = RcssAtRuleParser::default();
+        let warnings = Arc::new(RwLock::new(vec![]));
+        let mut flags = ParserFlags::empty();
+        flags.set(ParserFlags::CUSTOM_MEDIA, options.drafts.custom_media);
+        let parser_options = ParserOptions {
+            error_recovery: options.error_recovery,
+            warnings: options.error_recovery.then(|| warnings.clone()),
+            flags,
+            ..Default::default()
         };
-        Ok(this)
+        let style =
+            lightningcss::stylesheet::StyleSheet::parse_with(style, parser_options, &mut parser);
+        // Report error of @rcss rule instead of generic one.
+        // Rcss rules are not css, so they are not skipped by error recovery.
+        if let Some((rule_error, loc)) = parser.error.take() {
+            return Err(Error::WithLocation {
+                line: loc.line,
+                column: loc.column,
+                source: Box::new(rule_error.into()),
+            });
+        }
+        let style = style.map_err(|e| Error::from(e.into_owned()))?;
+        let warnings = warnings
+            .read()
+            .map(|warnings| warnings.iter().map(ToString::to_string).collect())
+            .unwrap_or_default();
+        // Hash minified form of stylesheet, so whitespace and comments doesn't affect scope id.
+        let mut normalized = style
+            .to_css(PrinterOptions {
+                minify: true,
+                ..Default::default()
+            })?
+            .code;
+        // Placeholders are the same for any constant or mixin, so their paths are hashed too.
+        for path in paths {
+            normalized.push('\0');
+            normalized.push_str(path);
+        }
+        Ok(Self {
+            random_ident: Self::init_random_class(&normalized, &options.naming.salt),
+            style,
+            warnings,
+        })
     }
     pub fn process_style(style: &str) -> Result<CssOutput> {
-        // Hide interpolation for now
-        let (interpolate, result) = crate::interpolate::handle_interpolate(&style);
-        let style = interpolate.unwrap_literals(result.as_ref());
-        let mut this = CssProcessor::new(&style)?;
-        this.process_style_inner()
+        Self::process_style_with_options(style, &CssProcessorOptions::default())
+    }
+
+    /// Process style with provided options (targets, naming, source map, etc.).
+    pub fn process_style_with_options(
+        style: &str,
+        options: &CssProcessorOptions,
+    ) -> Result<CssOutput> {
+        let (interpolate, result) = crate::interpolate::handle_interpolate(style);
+        let (unwrapped, interpolate_edits) = interpolate.unwrap_literals(result.as_ref());
+        let (includes, processed, include_edits) = crate::mixin::handle_includes(&unwrapped);
+        // Style from string literal is mapped back to literal source, to account for escapes.
+        let literal = options
+            .literal
+            .as_deref()
+            .and_then(|literal| Some((literal, source_map::unescape_literal(literal)?)))
+            .filter(|(_, (value, _))| value == style);
+        let (original, edits) = match literal {
+            Some((literal, (_, literal_edits))) => (literal, literal_edits.then(interpolate_edits)),
+            None => (style, interpolate_edits),
+        };
+        let edits = edits.then(include_edits);
+        let text = source_map::EditedText {
+            original,
+            processed: &processed,
+            edits: &edits,
+        };
+        // Errors are reported for preprocessed style, map them back to original text.
+        // Columns of lightningcss locations are counted from 1.
+        let map_error = |e: Error| {
+            e.map_location(|line, column| {
+                let (line, column) = text.original_position(line, column.saturating_sub(1));
+                (line, column + 1)
+            })
+        };
+        let paths = interpolate
+            .const_expressions()
+            .chain(includes.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+        let mut this = CssProcessor::new(&processed, options, &paths).map_err(map_error)?;
+        let mut output = this
+            .process_style_inner(options, &text)
+            .map_err(map_error)?;
+
+        // Replace placeholders of runtime expressions with scoped custom properties.
+        let suffix = output.class_suffix().to_owned();
+        let runtime_variables = interpolate
+            .runtime_expressions()
+            .enumerate()
+            .map(|(id, expr)| RuntimeVariable {
+                name: format!("--v{id}-{suffix}"),
+                expr: expr.to_owned(),
+                original_tokens: None,
+            })
+            .collect::<Vec<_>>();
+        // Replace in reverse order, to avoid replacing prefix of other placeholder.
+        for (id, var) in runtime_variables.iter().enumerate().rev() {
+            let placeholder = crate::interpolate::runtime_var_placeholder(id);
+            source_map::replace_tracked(
+                &mut output.css_data,
+                output.source_map.as_mut(),
+                &placeholder,
+                &var.name,
+            );
+        }
+        output.runtime_variables = runtime_variables;
+        output.const_variables = interpolate
+            .const_expressions()
+            .enumerate()
+            .map(|(id, path)| ConstVariable {
+                placeholder: crate::interpolate::const_placeholder(id),
+                path: path.to_owned(),
+                original_tokens: None,
+            })
+            .collect();
+        // Included mixins are resolved in the same way as interpolated constants.
+        let first_id = output.const_variables.len();
+        for (id, path) in includes.into_iter().enumerate() {
+            let declaration = crate::mixin::include_declaration(id, options.pretty);
+            let placeholder = crate::interpolate::const_placeholder(first_id + id);
+            source_map::replace_tracked(
+                &mut output.css_data,
+                output.source_map.as_mut(),
+                &declaration,
+                &placeholder,
+            );
+            // Mixins can include other mixins, their declarations are always printed in compact form.
+            let declaration = crate::mixin::include_declaration(id, false);
+            for mixin in output.mixins.values_mut() {
+                *mixin = mixin.replace(&declaration, &placeholder);
+            }
+            output.const_variables.push(ConstVariable {
+                placeholder,
+                path,
+                original_tokens: None,
+            });
+        }
+        Ok(output)
     }
 
-    fn process_style_inner<'a>(&mut self) -> Result<CssOutput> {
+    fn process_style_inner(
+        &mut self,
+        options: &CssProcessorOptions,
+        text: &source_map::EditedText,
+    ) -> Result<CssOutput> {
         // Create visitor that will modify class names, but will not modify css rules.
         let suffix = self.get_class_suffix();
+        let mut declarations = visitor::DeclarationsCollector::default();
+        self.style.visit(&mut declarations)?;
+        let renamer = naming::Renamer::new(
+            &options.naming,
+            &self.get_scoped_class(),
+            &suffix,
+            declarations.declared_struct.as_deref(),
+        );
+        // Keyframes and custom properties are renamed in same way as classes.
+        let keyframes = declarations
+            .keyframes
+            .into_iter()
+            .map(|name| {
+                let scoped = renamer.rename(&name);
+                (name, scoped)
+            })
+            .collect();
+        let custom_properties = if declarations.scope_vars {
+            declarations
+                .custom_properties
+                .into_iter()
+                .map(|name| {
+                    let scoped = renamer.rename(&name);
+                    (name, scoped)
+                })
+                .collect()
+        } else {
+            BTreeMap::new()
+        };
+        let mut scoped_names = global_names::ScopedNames::new();
+        for (kind, name) in declarations.scoped_names {
+            let scoped = renamer.rename(&name);
+            scoped_names.entry(kind).or_default().insert(name, scoped);
+        }
+        // Fallback to renaming, if style can't be wrapped in native `@scope`.
+        let native_scope = options.scoping == ScopingMode::Scope
+            && native_scope::is_supported(options.browsers)
+            && !declarations.requires_rewriting;
+        // Global style keeps its names, so only scope class is generated.
+        let (keyframes, custom_properties, scoped_names, state) = match options.scoping {
+            ScopingMode::Class | ScopingMode::Attribute | ScopingMode::Scope => (
+                keyframes,
+                custom_properties,
+                scoped_names,
+                Default::default(),
+            ),
+            ScopingMode::Global => (
+                BTreeMap::new(),
+                BTreeMap::new(),
+                BTreeMap::new(),
+                visitor::SelectorState::global(),
+            ),
+        };
+        let append_class = renamer.scope_class(&self.get_scoped_class());
+        // Inside of `@scope` classes can't conflict with other styles, so they keep their names.
+        let class_modify: Box<dyn FnMut(String) -> String> = if native_scope {
+            Box::new(|class| class)
+        } else {
+            Box::new(move |class| renamer.rename(&class))
+        };
+        // Attribute names are case-insensitive in html, so attribute is always in lowercase.
+        let scope_attr = (options.scoping == ScopingMode::Attribute)
+            .then(|| format!("data-rcss-{}", suffix.to_lowercase()));
         let mut visitor = visitor::SelectorVisitor {
-            append_class: self.get_scoped_class(),
-            class_modify: Box::new(move |class| format!("{class}-{suffix}")),
+            append_class,
+            scope_attr,
+            native_scope,
+            class_modify,
+            keyframes,
+            custom_properties,
+            scoped_names,
+            scope_ids: declarations.scope_ids,
             collect_classes: BTreeMap::new(),
+            collect_ids: BTreeMap::new(),
+            composes: BTreeMap::new(),
             declare: None,
             extend: None,
-            state: Default::default(),
+            extend_new_classes: false,
+            mixins: BTreeMap::new(),
+            scope_groups: vec![],
+            scope_limits: vec![],
+            state,
+            rule_depth: 0,
+            deep_found: false,
         };
         self.style.visit(&mut visitor)?;
+        if native_scope {
+            native_scope::wrap_rules(
+                &mut self.style.rules,
+                &visitor.scope_groups,
+                &visitor.append_class,
+                &visitor.scope_limits,
+            );
+        }
+        // Mixins are exported from struct, and can't depend on values known only at runtime.
+        if let Some(name) = visitor.mixins.keys().next() {
+            if visitor.declare.is_none() {
+                return Err(Error::MixinWithoutStruct(name.clone()));
+            }
+        }
+        if let Some((name, _)) = visitor
+            .mixins
+            .iter()
+            .find(|(_, mixin)| crate::interpolate::contains_runtime_var(mixin))
+        {
+            return Err(Error::RuntimeVariableInMixin(name.clone()));
+        }
+        let mut targets = Targets::from(options.browsers);
+        if options.drafts.nesting {
+            targets.include |= Features::Nesting;
+        }
+        if options.drafts.custom_media {
+            targets.include |= Features::CustomMediaQueries;
+        }
+        // Minify is needed to add vendor prefixes (and to resolve custom media),
+        // but it also optimize output, so by default use it only when it is needed.
+        if options.minify || options.browsers.is_some() || options.drafts != Drafts::default() {
+            self.style
+                .minify(MinifyOptions {
+                    targets,
+                    ..Default::default()
+                })
+                .map_err(|e| Error::MinifyFailed(e.to_string()))?;
+        }
+        // Local composed classes should be declared in the same style.
+        let composes = std::mem::take(&mut visitor.composes)
+            .into_iter()
+            .map(|(class, composed)| {
+                let composed = composed
+                    .into_iter()
+                    .map(|c| match c {
+                        ComposedClass::Local(name) => visitor
+                            .collect_classes
+                            .get(&name)
+                            .cloned()
+                            .map(ComposedClass::Local)
+                            .ok_or(Error::UnknownComposedClass(name)),
+                        c => Ok(c),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok((class, composed))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()?;
         let changed_classes = visitor
             .collect_classes
             .into_iter()
-            .map(|(k, v)| {
-                (
-                    k,
-                    ClassInfo {
-                        class_name: v,
-                        original_span: None,
-                    },
-                )
+            .map(|(k, v)| (k, ClassInfo::from(v)))
+            .collect::<BTreeMap<_, _>>();
+        let ids = visitor
+            .collect_ids
+            .into_iter()
+            .map(|(k, v)| (k, ClassInfo::from(v)))
+            .collect::<BTreeMap<_, _>>();
+        let keyframes = visitor
+            .keyframes
+            .into_iter()
+            .map(|(k, v)| (k, ClassInfo::from(v)))
+            .collect::<BTreeMap<_, _>>();
+        let custom_properties = visitor
+            .custom_properties
+            .into_iter()
+            .map(|(k, v)| (k, ClassInfo::from(v)))
+            .collect::<BTreeMap<_, _>>();
+        let scoped_names = visitor
+            .scoped_names
+            .into_iter()
+            .map(|(kind, names)| {
+                let names = names
+                    .into_iter()
+                    .map(|(k, v)| (k, ClassInfo::from(v)))
+                    .collect();
+                (kind, names)
             })
             .collect::<BTreeMap<_, _>>();
+        // Source map of style is tracked only for single line output.
+        if options.pretty && options.location.is_some() {
+            return Err(Error::SourceMapError(
+                "source map can't be generated for pretty printed style".to_owned(),
+            ));
+        }
+        let location = options.location.as_ref();
+        let mut printed_map = location.map(|_| parcel_sourcemap::SourceMap::new("/"));
+        let css_data = self
+            .style
+            .to_css(PrinterOptions {
+                minify: !options.pretty,
+                targets,
+                source_map: printed_map.as_mut(),
+                ..Default::default()
+            })?
+            .code;
+        let source_map = printed_map
+            .zip(location)
+            .map(|(map, location)| StyleSourceMap::from_printed(&map, location, text));
         Ok(CssOutput {
             uniq_class: visitor.append_class,
-            css_data: self
-                .style
-                .to_css(PrinterOptions {
-                    minify: true,
-                    ..Default::default()
-                })
-                .unwrap()
-                .code,
+            scope_attr: visitor.scope_attr,
+            class_suffix: suffix,
+            css_data,
+            source_map,
             declare: visitor.declare,
             extend: visitor.extend,
+            extend_new_classes: visitor.extend_new_classes,
+            mixins: visitor.mixins,
+            warnings: std::mem::take(&mut self.warnings),
             changed_classes,
+            ids,
+            keyframes,
+            custom_properties,
+            scoped_names,
+            composes,
+            runtime_variables: vec![],
+            const_variables: vec![],
         })
     }
+    /// Returns scope id, generated from content hash of style (mixed with optional salt).
     #[doc(hidden)]
-    pub fn init_random_class(style: &str) -> [char; 7] {
+    pub fn init_random_class(style: &str, salt: &str) -> [char; 7] {
         struct CssIdentChars;
         impl Distribution<char> for CssIdentChars {
             fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> char {
@@ -99,12 +428,12 @@ impl<'src> CssProcessor<'src> {
             }
         }
 
-        let mut seed = [0xdeu8; 32];
-        style
-            .bytes()
-            .filter(|c| !c.is_ascii_whitespace())
-            .enumerate()
-            .for_each(|(i, c)| seed[i % 32] ^= c);
+        // Salt is separated from style, to avoid ambiguity between salt suffix and style prefix.
+        let mut hasher = Sha256::new();
+        hasher.update(salt.as_bytes());
+        hasher.update([0]);
+        hasher.update(style.as_bytes());
+        let seed: [u8; 32] = hasher.finalize().into();
 
         let rng = rand_chacha::ChaCha8Rng::from_seed(seed);
 
@@ -128,6 +457,12 @@ impl<'src> CssProcessor<'src> {
     }
 }
 
+/// Parse browserslist query (like `"last 2 versions, not dead"`) into browser targets.
+/// Returns None if query doesn't resolve to any browser.
+pub fn browsers_from_query(query: &str) -> Result<Option<Browsers>> {
+    Browsers::from_browserslist([query]).map_err(|e| Error::BrowserslistError(e.to_string()))
+}
+
 #[derive(Clone, Debug)]
 pub struct ClassInfo {
     pub class_name: String,
@@ -142,13 +477,55 @@ impl From<String> for ClassInfo {
     }
 }
 
+/// Rust expression, that is interpolated into style through scoped css custom property.
+#[derive(Clone, Debug)]
+pub struct RuntimeVariable {
+    /// Name of custom property, that is used in style instead of expression (like `--v0-Mlfe`).
+    pub name: String,
+    /// Source code of rust expression.
+    pub expr: String,
+    /// Tokens of expression with their original spans (if available).
+    pub original_tokens: Option<proc_macro2::TokenStream>,
+}
+
+/// Path to rust constant, that is interpolated into style at compile time.
+#[derive(Clone, Debug)]
+pub struct ConstVariable {
+    /// Identifier that is used in style instead of constant value.
+    pub placeholder: String,
+    /// Source code of path to constant.
+    pub path: String,
+    /// Tokens of path with their original spans (if available).
+    pub original_tokens: Option<proc_macro2::TokenStream>,
+}
+
+/// Part of style, see [`CssOutput::style_parts`].
+#[derive(Clone, Copy, Debug)]
+pub enum StylePart<'a> {
+    Literal(&'a str),
+    Const(&'a ConstVariable),
+}
+
 #[derive(Debug)]
 pub struct CssOutput {
     uniq_class: String,
+    scope_attr: Option<String>,
+    class_suffix: String,
     css_data: String,
+    source_map: Option<StyleSourceMap>,
     declare: Option<syn::ItemStruct>,
     extend: Option<syn::Path>,
+    extend_new_classes: bool,
+    mixins: BTreeMap<String, String>,
+    warnings: Vec<String>,
     changed_classes: BTreeMap<String, ClassInfo>,
+    ids: BTreeMap<String, ClassInfo>,
+    keyframes: BTreeMap<String, ClassInfo>,
+    custom_properties: BTreeMap<String, ClassInfo>,
+    scoped_names: BTreeMap<NameKind, BTreeMap<String, ClassInfo>>,
+    composes: BTreeMap<String, Vec<ComposedClass>>,
+    runtime_variables: Vec<RuntimeVariable>,
+    const_variables: Vec<ConstVariable>,
 }
 
 impl CssOutput {
@@ -159,18 +536,35 @@ impl CssOutput {
         declare: Option<syn::ItemStruct>,
         extend: Option<syn::Path>,
         changed_classes: BTreeMap<String, ClassInfo>,
+        runtime_variables: Vec<RuntimeVariable>,
     ) -> Self {
         Self {
+            class_suffix: uniq_class.get(1..=4).unwrap_or_default().to_owned(),
             uniq_class,
+            scope_attr: None,
             css_data,
+            source_map: None,
             declare,
             extend,
+            extend_new_classes: false,
+            mixins: BTreeMap::new(),
+            warnings: vec![],
             changed_classes,
+            ids: BTreeMap::new(),
+            keyframes: BTreeMap::new(),
+            custom_properties: BTreeMap::new(),
+            scoped_names: BTreeMap::new(),
+            composes: BTreeMap::new(),
+            runtime_variables,
+            const_variables: vec![],
         }
     }
     /// Removes styles from output.
     pub fn clear_styles(&mut self) {
         self.css_data.clear();
+        self.const_variables.clear();
+        self.source_map = None;
+        self.mixins.values_mut().for_each(String::clear);
     }
 
     #[doc(hidden)]
@@ -181,6 +575,52 @@ impl CssOutput {
     pub fn classes_map(&self) -> &BTreeMap<String, ClassInfo> {
         &self.changed_classes
     }
+    #[doc(hidden)]
+    pub fn classes_map_mut(&mut self) -> &mut BTreeMap<String, ClassInfo> {
+        &mut self.changed_classes
+    }
+
+    /// Returns map of scoped ids, if style uses `@rcss(scope-ids)`.
+    pub fn ids_map(&self) -> &BTreeMap<String, ClassInfo> {
+        &self.ids
+    }
+    #[doc(hidden)]
+    pub fn ids_map_mut(&mut self) -> &mut BTreeMap<String, ClassInfo> {
+        &mut self.ids
+    }
+
+    /// Returns map of scoped `@keyframes` names.
+    pub fn keyframes_map(&self) -> &BTreeMap<String, ClassInfo> {
+        &self.keyframes
+    }
+    #[doc(hidden)]
+    pub fn keyframes_map_mut(&mut self) -> &mut BTreeMap<String, ClassInfo> {
+        &mut self.keyframes
+    }
+
+    /// Returns map of scoped custom properties (like `--accent`), if style uses `@rcss(scope-vars)`.
+    pub fn custom_properties_map(&self) -> &BTreeMap<String, ClassInfo> {
+        &self.custom_properties
+    }
+    #[doc(hidden)]
+    pub fn custom_properties_map_mut(&mut self) -> &mut BTreeMap<String, ClassInfo> {
+        &mut self.custom_properties
+    }
+
+    /// Returns map of scoped names of `kind` (like layers or containers), declared in style.
+    pub fn scoped_names_map(&self, kind: NameKind) -> Option<&BTreeMap<String, ClassInfo>> {
+        self.scoped_names.get(&kind)
+    }
+    #[doc(hidden)]
+    pub fn scoped_names_map_mut(&mut self, kind: NameKind) -> &mut BTreeMap<String, ClassInfo> {
+        self.scoped_names.entry(kind).or_default()
+    }
+
+    /// Returns classes composed into other classes with `composes:` (by original class name).
+    /// Local classes are already scoped.
+    pub fn composes_map(&self) -> &BTreeMap<String, Vec<ComposedClass>> {
+        &self.composes
+    }
 
     /// Returns mod name if css should emit mod instead of inline struct.
     pub fn declare(&self) -> Option<syn::ItemStruct> {
@@ -190,16 +630,100 @@ impl CssOutput {
     pub fn extend(&self) -> Option<syn::Path> {
         self.extend.clone()
     }
+    /// Returns true if extension is allowed to add classes, that parent style doesn't have.
+    pub fn extend_new_classes(&self) -> bool {
+        self.extend_new_classes
+    }
+    #[doc(hidden)]
+    pub fn set_extend_new_classes(&mut self, new_classes: bool) {
+        self.extend_new_classes = new_classes;
+    }
 
+    /// Returns declarations of mixins, exported from style with `@rcss(mixin name)` (by mixin name).
+    /// Declarations can contain placeholders of interpolated constants, see [`Self::split_consts`].
+    pub fn mixins(&self) -> &BTreeMap<String, String> {
+        &self.mixins
+    }
+    #[doc(hidden)]
+    pub fn mixins_mut(&mut self) -> &mut BTreeMap<String, String> {
+        &mut self.mixins
+    }
+
+    /// Returns invalid rules and declarations, that were skipped with error recovery.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Returns list of runtime expressions, that was used in `${..}` interpolation.
+    pub fn runtime_variables(&self) -> &[RuntimeVariable] {
+        &self.runtime_variables
+    }
+    #[doc(hidden)]
+    pub fn runtime_variables_mut(&mut self) -> &mut [RuntimeVariable] {
+        &mut self.runtime_variables
+    }
+
+    /// Returns list of constants, that was used in `${..}` interpolation.
+    pub fn const_variables(&self) -> &[ConstVariable] {
+        &self.const_variables
+    }
+    #[doc(hidden)]
+    pub fn const_variables_mut(&mut self) -> &mut [ConstVariable] {
+        &mut self.const_variables
+    }
+
+    /// Returns source map of style, if it was processed with location of style in rust source.
+    pub fn source_map(&self) -> Option<&StyleSourceMap> {
+        self.source_map.as_ref()
+    }
+
+    /// Returns style string, with placeholders of interpolated constants.
     pub fn style_string(&self) -> String {
         self.css_data.clone()
     }
 
+    /// Split style by interpolated constants.
+    /// Literal parts should be concatenated with values of constants in the same order.
+    pub fn style_parts(&self) -> Vec<StylePart<'_>> {
+        self.split_consts(&self.css_data)
+    }
+
+    /// Split text of style (or mixin) by interpolated constants.
+    pub fn split_consts<'a>(&'a self, text: &'a str) -> Vec<StylePart<'a>> {
+        let mut parts = vec![];
+        let mut rest = text;
+        loop {
+            // Find nearest placeholder, constants can be reordered by minifier.
+            let next = self
+                .const_variables
+                .iter()
+                .filter_map(|var| rest.find(&var.placeholder).map(|pos| (pos, var)))
+                .min_by_key(|(pos, _)| *pos);
+            let Some((pos, var)) = next else {
+                break;
+            };
+            if pos > 0 {
+                parts.push(StylePart::Literal(&rest[..pos]));
+            }
+            parts.push(StylePart::Const(var));
+            rest = &rest[pos + var.placeholder.len()..];
+        }
+        if !rest.is_empty() {
+            parts.push(StylePart::Literal(rest));
+        }
+        parts
+    }
+
     pub fn class_name(&self) -> &str {
         &self.uniq_class
     }
+    /// Returns name of attribute that scopes style (like `data-rcss-sauj`), if style uses attribute scoping.
+    pub fn scope_attr(&self) -> Option<&str> {
+        self.scope_attr.as_deref()
+    }
+    /// Returns suffix that is used in scoped names (doesn't depend on naming strategy).
     pub fn class_suffix(&self) -> &str {
-        &self.uniq_class[1..=4]
+        &self.class_suffix
     }
 
     pub fn merge_to_string(styles: &[Self]) -> String {
@@ -222,6 +746,122 @@ impl CssOutput {
 #[cfg(test)]
 mod tests {
 
+    #[test]
+    fn check_scope_id_hash() {
+        use super::CssProcessor;
+        // Permutation of the same bytes used to produce the same id.
+        let first = CssProcessor::init_random_class(".ab{color:red}", "");
+        let second = CssProcessor::init_random_class(".ba{color:red}", "");
+        assert_ne!(first, second);
+        assert_eq!(first, CssProcessor::init_random_class(".ab{color:red}", ""));
+        let salted = CssProcessor::init_random_class(".ab{color:red}", "my_crate__theme");
+        assert_ne!(first, salted);
+    }
+
+    #[test]
+    fn check_scope_id_ignores_formatting() {
+        let style = ".my-class { color: red; }";
+        let formatted = r#"
+        /* Comment */
+        .my-class {
+            color:   red;
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let formatted = super::CssProcessor::process_style(formatted).unwrap();
+        assert_eq!(output.class_name(), formatted.class_name());
+        assert_eq!(
+            output.classes_map()["my-class"].class_name,
+            formatted.classes_map()["my-class"].class_name
+        );
+        let changed = super::CssProcessor::process_style(".my-class { color: blue; }").unwrap();
+        assert_ne!(output.class_name(), changed.class_name());
+    }
+
+    #[test]
+    fn check_source_map() {
+        use super::SourceLocation;
+        let style = ".a { color: ${color}; }\n.b { color: blue; }";
+        let location = SourceLocation {
+            file: "src/lib.rs".into(),
+            line: 10,
+            column: 4,
+        };
+        let options = super::CssProcessorOptions::new().source_map(location);
+        let output = super::CssProcessor::process_style_with_options(style, &options).unwrap();
+        let map = output.source_map().unwrap();
+        assert_eq!(map.file, "src/lib.rs");
+        let position = |line, column| {
+            map.mappings
+                .iter()
+                .find(|m| m.original_line == line && m.original_column == column)
+                .map(|m| m.generated_column as usize)
+                .unwrap()
+        };
+        // Positions in generated css are shifted after runtime variables are renamed.
+        assert!(output.css_data[position(10, 4)..].starts_with(".a-"));
+        assert!(output.css_data[position(11, 0)..].starts_with(".b-"));
+    }
+
+    #[test]
+    fn check_source_map_after_interpolation() {
+        use super::SourceLocation;
+        let style = ".a { color: ${color}; } .b { color: blue; }";
+        let location = SourceLocation {
+            file: "src/lib.rs".into(),
+            line: 10,
+            column: 4,
+        };
+        let options = super::CssProcessorOptions::new().source_map(location);
+        let output = super::CssProcessor::process_style_with_options(style, &options).unwrap();
+        let map = output.source_map().unwrap();
+        let column = style.find(".b").unwrap() as u32 + 4;
+        let mapping = map
+            .mappings
+            .iter()
+            .find(|m| m.original_line == 10 && m.original_column == column)
+            .unwrap();
+        // Column of selector after interpolation points to its position in source.
+        assert!(output.css_data[mapping.generated_column as usize..].starts_with(".b-"));
+    }
+
+    #[test]
+    fn check_source_map_of_escaped_literal() {
+        use super::SourceLocation;
+        let literal = r#""/* \"x\" */ .a { color: red; }""#;
+        let style = r#"/* "x" */ .a { color: red; }"#;
+        let location = SourceLocation {
+            file: "src/lib.rs".into(),
+            line: 10,
+            column: 4,
+        };
+        let options = super::CssProcessorOptions::new()
+            .source_map(location)
+            .literal(literal);
+        let output = super::CssProcessor::process_style_with_options(style, &options).unwrap();
+        let map = output.source_map().unwrap();
+        // Column points to selector in literal, after escaped quotes.
+        let column = literal.find(".a").unwrap() as u32 + 4;
+        assert!(map
+            .mappings
+            .iter()
+            .any(|m| m.original_line == 10 && m.original_column == column));
+
+        // Errors are reported relative to literal too.
+        let literal = r#""/* \"\" */ .a..b { }""#;
+        let style = r#"/* "" */ .a..b { }"#;
+        let options = super::CssProcessorOptions::new().literal(literal);
+        let error = super::CssProcessor::process_style_with_options(style, &options).unwrap_err();
+        let value_location = super::CssProcessor::process_style(style)
+            .unwrap_err()
+            .location()
+            .unwrap();
+        let location = error.location().unwrap();
+        assert_eq!(location.line, 0);
+        // Shifted by opening quote and two escapes.
+        assert_eq!(location.column, value_location.column + 3);
+    }
+
     #[test]
     fn check_process_class_names() {
         let style = r#"
@@ -238,6 +878,628 @@ mod tests {
         assert_eq!(output.css_data, output_css)
     }
     #[test]
+    fn check_targets_lowering() {
+        let style = r#"
+        .my-class {
+            user-select: none;
+            &:hover {
+                color: red;
+            }
+        }
+        "#;
+        let browsers = super::browsers_from_query("safari 13").unwrap();
+        let options = super::CssProcessorOptions::new().targets(browsers);
+        let output = super::CssProcessor::process_style_with_options(style, &options).unwrap();
+        let suffix = output.class_suffix();
+        let output_css = format!(
+            r#".my-class-{suffix}{{-webkit-user-select:none;user-select:none}}.my-class-{suffix}:hover{{color:red}}"#
+        );
+        assert_eq!(output.css_data, output_css)
+    }
+
+    #[test]
+    fn check_runtime_interpolation() {
+        let style = r#"
+        .my-class {
+            color: ${color};
+            padding: ${"1em"} ${padding * 2};
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let suffix = output.class_suffix();
+        let vars = output.runtime_variables();
+        assert_eq!(vars.len(), 2);
+        assert_eq!(vars[0].name, format!("--v0-{suffix}"));
+        assert_eq!(vars[0].expr, "color");
+        assert_eq!(vars[1].name, format!("--v1-{suffix}"));
+        assert_eq!(vars[1].expr, "padding * 2");
+        let output_css = format!(
+            r#".my-class-{suffix}{{color:var(--v0-{suffix});padding:1em var(--v1-{suffix})}}"#
+        );
+        assert_eq!(output.css_data, output_css)
+    }
+
+    #[test]
+    fn check_const_interpolation() {
+        let style = r#"
+        .my-class {
+            color: ${crate::theme::PRIMARY};
+            width: ${SIZE}px;
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let suffix = output.class_suffix();
+        assert!(output.runtime_variables().is_empty());
+        let parts = output
+            .style_parts()
+            .into_iter()
+            .map(|part| match part {
+                super::StylePart::Literal(s) => s.to_owned(),
+                super::StylePart::Const(var) => format!("<{}>", var.path),
+            })
+            .collect::<String>();
+        assert_eq!(
+            parts,
+            format!(".my-class-{suffix}{{color:<crate::theme::PRIMARY>;width:<SIZE>px}}")
+        );
+    }
+
+    #[test]
+    fn check_static_interpolation_is_const() {
+        // Statics are named like constants, so they are inlined at compile time too,
+        // only other expressions (like function calls) are evaluated on each `vars_style()` call.
+        let style = r#"
+        @rcss(pub struct Theme);
+        .my-class {
+            color: ${ACCENT};
+            background: ${accent()};
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let consts = output
+            .const_variables()
+            .iter()
+            .map(|var| var.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(consts, ["ACCENT"]);
+        let vars = output.runtime_variables();
+        assert_eq!(vars.len(), 1);
+        assert_eq!(vars[0].expr, "accent()");
+    }
+
+    #[test]
+    fn check_const_paths_change_scope() {
+        let primary = ".button { color: ${theme::PRIMARY}; }";
+        let secondary = ".button { color: ${theme::SECONDARY}; }";
+        let primary = super::CssProcessor::process_style(primary).unwrap();
+        let secondary = super::CssProcessor::process_style(secondary).unwrap();
+        assert_ne!(primary.class_name(), secondary.class_name());
+
+        let focus = ".button { @include Base::focus; }";
+        let reset = ".button { @include Base::reset; }";
+        let focus = super::CssProcessor::process_style(focus).unwrap();
+        let reset = super::CssProcessor::process_style(reset).unwrap();
+        assert_ne!(focus.class_name(), reset.class_name());
+    }
+
+    #[test]
+    fn check_keyframes_scoping() {
+        let style = r#"
+        .spinner {
+            animation: spin 1s linear infinite;
+        }
+        .fade {
+            animation-name: fade-in, global-anim;
+        }
+        @keyframes spin {
+            from { transform: rotate(0deg); }
+            to { transform: rotate(360deg); }
+        }
+        @keyframes fade-in {
+            from { opacity: 0; }
+            to { opacity: 1; }
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let suffix = output.class_suffix();
+        let keyframes = output.keyframes_map();
+        assert_eq!(keyframes["spin"].class_name, format!("spin-{suffix}"));
+        assert_eq!(keyframes["fade-in"].class_name, format!("fade-in-{suffix}"));
+        let mut output_css = String::new();
+        output_css.push_str(&format!(
+            ".spinner-{suffix}{{animation:1s linear infinite spin-{suffix}}}"
+        ));
+        output_css.push_str(&format!(
+            ".fade-{suffix}{{animation-name:fade-in-{suffix},global-anim}}"
+        ));
+        output_css.push_str(&format!(
+            "@keyframes spin-{suffix}{{0%{{transform:rotate(0)}}to{{transform:rotate(360deg)}}}}"
+        ));
+        output_css.push_str(&format!(
+            "@keyframes fade-in-{suffix}{{0%{{opacity:0}}to{{opacity:1}}}}"
+        ));
+        assert_eq!(output.css_data, output_css)
+    }
+
+    #[test]
+    fn check_scope_vars() {
+        let style = r#"
+        @rcss(scope-vars);
+        .card {
+            --accent: red;
+            color: var(--accent);
+            background: var(--global-bg, var(--accent));
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let suffix = output.class_suffix();
+        let vars = output.custom_properties_map();
+        assert_eq!(vars.len(), 1);
+        assert_eq!(vars["--accent"].class_name, format!("--accent-{suffix}"));
+        let output_css = format!(
+            ".card-{suffix}{{--accent-{suffix}:red;color:var(--accent-{suffix});background:var(--global-bg,var(--accent-{suffix}))}}"
+        );
+        assert_eq!(output.css_data, output_css)
+    }
+
+    #[test]
+    fn check_scoped_names() {
+        use super::NameKind;
+        let style = r#"
+        @layer base, theme.dark;
+        @layer base {
+            .card { color: red; }
+        }
+        .list {
+            container: sidebar / inline-size;
+            counter-reset: item;
+            grid-template-areas: "head head" "nav main";
+            view-transition-name: hero;
+        }
+        .item {
+            counter-increment: item;
+            grid-area: nav;
+            grid-column-end: main-end;
+        }
+        .item::before {
+            content: counter(item) ". " counter(global-counter);
+        }
+        @container sidebar (min-width: 400px) {
+            .item { color: blue; }
+        }
+        @container other (min-width: 400px) {
+            .item { color: green; }
+        }
+        ::view-transition-old(hero) {
+            animation-duration: 1s;
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let suffix = output.class_suffix();
+        let scoped = |kind, name: &str| {
+            output.scoped_names_map(kind).unwrap()[name]
+                .class_name
+                .clone()
+        };
+        assert_eq!(scoped(NameKind::Layer, "base"), format!("base-{suffix}"));
+        assert_eq!(scoped(NameKind::Layer, "theme"), format!("theme-{suffix}"));
+        assert_eq!(
+            scoped(NameKind::Container, "sidebar"),
+            format!("sidebar-{suffix}")
+        );
+        assert_eq!(scoped(NameKind::Counter, "item"), format!("item-{suffix}"));
+        assert_eq!(scoped(NameKind::GridArea, "nav"), format!("nav-{suffix}"));
+        assert_eq!(
+            scoped(NameKind::ViewTransition, "hero"),
+            format!("hero-{suffix}")
+        );
+        let css = &output.css_data;
+        assert!(css.starts_with(&format!(
+            "@layer base-{suffix},theme-{suffix}.dark;@layer base-{suffix}{{"
+        )));
+        assert!(css.contains(&format!("container:sidebar-{suffix}/inline-size")));
+        assert!(css.contains(&format!("counter-reset:item-{suffix}")));
+        assert!(css.contains(&format!(
+            "grid-template-areas:\"head-{suffix} head-{suffix}\"\"nav-{suffix} main-{suffix}\""
+        )));
+        assert!(css.contains(&format!("view-transition-name:hero-{suffix}")));
+        assert!(css.contains(&format!("counter-increment:item-{suffix}")));
+        assert!(css.contains(&format!("grid-area:nav-{suffix}")));
+        assert!(css.contains(&format!("grid-column-end:main-{suffix}-end")));
+        assert!(css.contains(&format!(
+            "content:counter(item-{suffix})\". \" counter(global-counter)"
+        )));
+        assert!(css.contains(&format!("@container sidebar-{suffix} (")));
+        assert!(css.contains("@container other ("));
+        // View transition pseudo-elements are not scoped with scope class.
+        assert!(css.contains(&format!("::view-transition-old(hero-{suffix}){{")));
+        assert!(!css.contains(&format!(".{}::view-transition", output.uniq_class)));
+    }
+
+    #[test]
+    fn check_scope_ids() {
+        let style = r#"
+        @rcss(scope-ids);
+        #dialog {
+            color: red;
+        }
+        #dialog > .title, :is(#close) {
+            color: blue;
+        }
+        :global(#app) div {
+            color: green;
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let suffix = output.class_suffix();
+        let ids = output.ids_map();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids["dialog"].class_name, format!("dialog-{suffix}"));
+        assert_eq!(ids["close"].class_name, format!("close-{suffix}"));
+        let scope = output.class_name();
+        let output_css = format!(
+            "#dialog-{suffix}{{color:red}}#dialog-{suffix}>.title-{suffix},#close-{suffix}{{color:#00f}}#app div.{scope}{{color:green}}"
+        );
+        assert_eq!(output.css_data, output_css);
+
+        // Without `@rcss(scope-ids)` ids are kept as is, and scoped with scope class.
+        let output = super::CssProcessor::process_style("#dialog { color: red; }").unwrap();
+        assert!(output.ids_map().is_empty());
+        let output_css = format!("#dialog.{}{{color:red}}", output.class_name());
+        assert_eq!(output.css_data, output_css);
+    }
+
+    #[test]
+    fn check_naming_strategies() {
+        use super::{Naming, NamingStrategy};
+        let style = r#"
+        @rcss(pub struct Button);
+        .primary {
+            animation: spin 1s;
+        }
+        div {
+            color: red;
+        }
+        @keyframes spin {
+            to { opacity: 0; }
+        }
+        "#;
+        let naming = Naming {
+            strategy: NamingStrategy::Readable,
+            module_path: "my_crate__theme".into(),
+            ..Default::default()
+        };
+        let options = super::CssProcessorOptions::new().naming(naming);
+        let output = super::CssProcessor::process_style_with_options(style, &options).unwrap();
+        // Suffix is kept, since structs with the same name can be declared in different inline modules.
+        let suffix = output.class_suffix().to_owned();
+        assert_eq!(
+            output.class_name(),
+            format!("my_crate__theme__Button-{suffix}")
+        );
+        assert_eq!(
+            output.classes_map()["primary"].class_name,
+            format!("my_crate__theme__Button__primary-{suffix}")
+        );
+        assert_eq!(
+            output.css_data,
+            format!(
+                ".my_crate__theme__Button__primary-{suffix}{{animation:1s my_crate__theme__Button__spin-{suffix}}}\
+                div.my_crate__theme__Button-{suffix}{{color:red}}\
+                @keyframes my_crate__theme__Button__spin-{suffix}{{to{{opacity:0}}}}"
+            )
+        );
+
+        let naming = Naming {
+            strategy: NamingStrategy::Minified,
+            ..Default::default()
+        };
+        let options = super::CssProcessorOptions::new().naming(naming);
+        let output = super::CssProcessor::process_style_with_options(style, &options).unwrap();
+        let primary = &output.classes_map()["primary"].class_name;
+        assert_eq!(primary.len(), 5);
+        assert!(output.css_data.starts_with(&format!(".{primary}{{")));
+    }
+
+    #[test]
+    fn check_vars_not_scoped_by_default() {
+        let style = r#"
+        .card {
+            --accent: red;
+            color: var(--accent);
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let suffix = output.class_suffix();
+        assert!(output.custom_properties_map().is_empty());
+        let output_css = format!(".card-{suffix}{{--accent:red;color:var(--accent)}}");
+        assert_eq!(output.css_data, output_css)
+    }
+
+    #[test]
+    fn check_composes() {
+        let style = r#"
+        .base {
+            padding: 1em;
+        }
+        .button {
+            composes: base;
+            composes: icon from ::path::to::Icons;
+            composes: btn from global;
+            color: red;
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let suffix = output.class_suffix();
+        let composed = &output.composes_map()["button"];
+        assert!(
+            matches!(&composed[0], super::ComposedClass::Local(c) if *c == format!("base-{suffix}"))
+        );
+        assert!(
+            matches!(&composed[1], super::ComposedClass::External { class, path }
+            if class == "icon" && quote::quote!(#path).to_string() == ":: path :: to :: Icons")
+        );
+        assert!(matches!(&composed[2], super::ComposedClass::Global(c) if c == "btn"));
+        let output_css = format!(".base-{suffix}{{padding:1em}}.button-{suffix}{{color:red}}");
+        assert_eq!(output.css_data, output_css)
+    }
+
+    #[test]
+    fn check_composes_errors() {
+        let style = r#"
+        .button, .link {
+            composes: base;
+        }
+        "#;
+        let err = super::CssProcessor::process_style(style).unwrap_err();
+        assert!(matches!(err, super::Error::WithLocation { source, .. }
+            if matches!(*source, super::Error::ComposesNotAllowed)));
+        let style = r#"
+        .button {
+            composes: base;
+        }
+        "#;
+        let err = super::CssProcessor::process_style(style).unwrap_err();
+        assert!(matches!(err, super::Error::UnknownComposedClass(c) if c == "base"));
+    }
+
+    #[test]
+    fn check_rcss_rule_errors() {
+        let check = |style: &str| {
+            let err = super::CssProcessor::process_style(style).unwrap_err();
+            let loc = err.location().unwrap();
+            let super::Error::WithLocation { source, .. } = err else {
+                unreachable!()
+            };
+            (*source, loc.line)
+        };
+        let (err, line) = check("@rcss(pub struct A);\n@rcss(pub struct B);");
+        assert!(matches!(err, super::Error::DuplicateDeclare));
+        assert_eq!(line, 1);
+        let (err, _) = check("@rcss(extend ::a::A);\n@rcss(extend ::b::B);");
+        assert!(matches!(err, super::Error::DuplicateExtend));
+        let (err, _) = check("@rcss(pub struct A { class: String });");
+        assert!(matches!(err, super::Error::StructWithFields(name) if name == "A"));
+        let (err, _) = check("@rcss(pub struct A(String));");
+        assert!(matches!(err, super::Error::StructWithFields(name) if name == "A"));
+        let (err, _) = check(".a { color: red; }\n@rcss(scope-var);");
+        assert!(matches!(err, super::Error::UnknownRcssRule(rule) if rule == "scope - var"));
+
+        // Declare and extend can be used together.
+        super::CssProcessor::process_style("@rcss(pub struct A);\n@rcss(extend ::b::B);").unwrap();
+    }
+
+    #[test]
+    fn check_processor_options() {
+        use super::{CssProcessor, CssProcessorOptions, Drafts, ScopingMode};
+        let style = r#"
+        @custom-media --narrow (max-width: 30em);
+        .a {
+            color: red;
+            & b { color: blue; }
+        }
+        .a { margin: 0; }
+        @media (--narrow) { .a { padding: 0 } }
+        "#;
+        let process = |options: CssProcessorOptions| {
+            CssProcessor::process_style_with_options(style, &options)
+        };
+
+        // Custom media is a draft, so it is rejected by default.
+        assert!(process(CssProcessorOptions::new()).is_err());
+        let drafts = Drafts {
+            nesting: true,
+            custom_media: true,
+        };
+        let output = process(CssProcessorOptions::new().drafts(drafts)).unwrap();
+        let a = &output.classes_map()["a"].class_name;
+        let scope = output.class_name();
+        assert_eq!(
+            output.css_data,
+            format!(
+                ".{a}{{color:red}}.{a} b.{scope}{{color:#00f}}.{a}{{margin:0}}\
+                @media (width<=30em){{.{a}{{padding:0}}}}"
+            )
+        );
+
+        let output = process(CssProcessorOptions::new().drafts(drafts).pretty(true)).unwrap();
+        assert!(output
+            .css_data
+            .starts_with(&format!(".{a} {{\n  color: red;")));
+
+        // Unknown rules are skipped with error recovery.
+        let output = process(CssProcessorOptions::new().error_recovery(true)).unwrap();
+        assert_eq!(output.warnings().len(), 1);
+        let a = &output.classes_map()["a"].class_name;
+        assert!(output.css_data.starts_with(&format!(".{a}{{color:red;")));
+
+        let output = process(
+            CssProcessorOptions::new()
+                .drafts(drafts)
+                .scoping(ScopingMode::Global),
+        )
+        .unwrap();
+        assert!(output.classes_map().is_empty());
+        assert!(output.css_data.starts_with(".a{color:red}.a b{color:#00f}"));
+
+        let err = process(
+            CssProcessorOptions::new()
+                .drafts(drafts)
+                .pretty(true)
+                .source_map(Default::default()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, super::Error::SourceMapError(_)));
+    }
+
+    #[test]
+    fn check_attribute_scoping() {
+        use super::{CssProcessor, CssProcessorOptions, ScopingMode};
+        let style = r#"
+        .a div { color: red; }
+        #id, :scope { color: blue; }
+        :host-context(.dark) p { color: white; }
+        "#;
+        let options = CssProcessorOptions::new().scoping(ScopingMode::Attribute);
+        let output = CssProcessor::process_style_with_options(style, &options).unwrap();
+        let a = &output.classes_map()["a"].class_name;
+        let attr = output.scope_attr().unwrap();
+        assert_eq!(
+            attr,
+            format!("data-rcss-{}", output.class_suffix().to_lowercase())
+        );
+        assert_eq!(
+            output.css_data,
+            format!(
+                ".{a} div[{attr}]{{color:red}}#id[{attr}],[{attr}]{{color:#00f}}\
+                :is(.dark [{attr}],.dark[{attr}]) p[{attr}]{{color:#fff}}"
+            )
+        );
+        assert_eq!(
+            CssProcessor::process_style(style).unwrap().scope_attr(),
+            None
+        );
+    }
+
+    #[test]
+    fn check_host_context_attribute_scoping() {
+        use super::{CssProcessor, CssProcessorOptions, ScopingMode};
+        let style = ":host-context(.dark) { color: white; }";
+        let options = CssProcessorOptions::new().scoping(ScopingMode::Attribute);
+        let output = CssProcessor::process_style_with_options(style, &options).unwrap();
+        let attr = output.scope_attr().unwrap();
+        // Root is matched both inside of `.dark` ancestor, and with `.dark` on itself.
+        assert_eq!(
+            output.css_data,
+            format!(":is(.dark [{attr}],.dark[{attr}]){{color:#fff}}")
+        );
+    }
+
+    #[test]
+    fn check_native_scope() {
+        use super::{browsers_from_query, CssProcessor, CssProcessorOptions, ScopingMode};
+        let style = r#"
+        @keyframes spin { to { rotate: 1turn } }
+        .a div { color: red; animation: spin 1s; }
+        .slot :deep(.item) { color: blue; }
+        :host(.active), :host-context(.dark) p { color: white; }
+        @global { body { margin: 0 } }
+        "#;
+        let options = CssProcessorOptions::new().scoping(ScopingMode::Scope);
+        let output = CssProcessor::process_style_with_options(style, &options).unwrap();
+        let scope = output.class_name();
+        let spin = &output.keyframes_map()["spin"].class_name;
+        assert_eq!(output.classes_map()["a"].class_name, "a");
+        assert_eq!(output.classes_map()["slot"].class_name, "slot");
+        assert_eq!(
+            output.css_data,
+            format!(
+                "@keyframes {spin}{{to{{rotate:1turn}}}}\
+                @scope(.{scope}) to (.slot>*){{.a div{{color:red;animation:1s {spin}}}}}\
+                @scope(.{scope}){{.slot .item{{color:#00f}}}}\
+                @scope(.{scope}) to (.slot>*){{:scope.active,:is(.dark :scope,.dark:scope) p{{color:#fff}}}}\
+                body{{margin:0}}"
+            )
+        );
+
+        // Inline `:global(..)` is not relative to scope root, so style falls back to renaming.
+        let fallback = ":global(.dark) .a { color: red; }";
+        let output = CssProcessor::process_style_with_options(fallback, &options).unwrap();
+        let a = &output.classes_map()["a"].class_name;
+        assert_ne!(a, "a");
+        assert_eq!(output.css_data, format!(".dark .{a}{{color:red}}"));
+
+        // The same for browsers without `@scope` support.
+        let options = options.targets(browsers_from_query("safari 15").unwrap());
+        let output = CssProcessor::process_style_with_options(style, &options).unwrap();
+        assert!(!output.css_data.contains("@scope"));
+        assert_ne!(output.classes_map()["a"].class_name, "a");
+    }
+
+    #[test]
+    fn check_mixins() {
+        let style = r#"
+        @rcss(pub struct Base);
+        @rcss(mixin focus_ring) {
+            outline: 2px solid ${crate::theme::ACCENT};
+            @include Reset::base;
+        }
+        .button {
+            @include crate::Base::focus_ring;
+            color: red;
+            @include Reset::base
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let class = output.classes_map()["button"].class_name.clone();
+        assert_eq!(
+            output.css_data,
+            format!(".{class}{{__RCSS__CONST_2__;color:red;__RCSS__CONST_3__}}")
+        );
+        assert_eq!(
+            output.mixins()["focus_ring"],
+            "outline:2px solid __RCSS__CONST_0__;__RCSS__CONST_1__"
+        );
+        let paths = output
+            .const_variables()
+            .iter()
+            .map(|var| var.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "crate::theme::ACCENT",
+                "Reset::base",
+                "crate::Base::focus_ring",
+                "Reset::base"
+            ]
+        );
+        // Placeholders are kept in place, when style is minified for targets.
+        let browsers = super::browsers_from_query("defaults").unwrap();
+        let options = super::CssProcessorOptions::new().targets(browsers);
+        let output = super::CssProcessor::process_style_with_options(style, &options).unwrap();
+        assert_eq!(
+            output.css_data,
+            format!(".{class}{{__RCSS__CONST_2__;color:red;__RCSS__CONST_3__}}")
+        );
+
+        let err = super::CssProcessor::process_style("@rcss(mixin a) { color: red; }").unwrap_err();
+        assert!(matches!(err, super::Error::MixinWithoutStruct(name) if name == "a"));
+        let err = super::CssProcessor::process_style(
+            "@rcss(pub struct A);\n@rcss(mixin a) { color: ${color}; }",
+        )
+        .unwrap_err();
+        assert!(matches!(err, super::Error::RuntimeVariableInMixin(name) if name == "a"));
+        let err = super::CssProcessor::process_style(
+            "@rcss(pub struct A);\n@rcss(mixin a) {}\n@rcss(mixin a) {}",
+        )
+        .unwrap_err();
+        let super::Error::WithLocation { source, .. } = err else {
+            unreachable!()
+        };
+        assert!(matches!(*source, super::Error::DuplicateMixin(name) if name == "a"));
+    }
+    #[test]
     fn check_global_selector() {
         let style = r#"
         :global(.my-class) {
@@ -254,6 +1516,255 @@ mod tests {
         assert_eq!(output.css_data, output_css)
     }
     #[test]
+    fn check_global_blocks() {
+        let style = r#"
+        :global {
+            .reset, b {
+                margin: 0;
+            }
+        }
+        @global {
+            .body {
+                color: red;
+            }
+        }
+        .card {
+            color: red;
+            :global {
+                .icon {
+                    color: blue;
+                }
+            }
+            @global {
+                .title {
+                    color: green;
+                }
+            }
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let suffix = output.class_suffix();
+        let mut output_css = String::new();
+        output_css.push_str(r#".reset,b{margin:0}"#);
+        output_css.push_str(r#".body{color:red}"#);
+        output_css.push_str(&format!(
+            r#".card-{suffix}{{color:red;& .icon{{color:#00f}}& .title{{color:green}}}}"#
+        ));
+        assert_eq!(output.css_data, output_css);
+        assert_eq!(output.classes_list().collect::<Vec<_>>(), vec!["card"]);
+
+        let style = r#"
+        :global {
+            color: red;
+        }
+        "#;
+        let err = super::CssProcessor::process_style(style).unwrap_err();
+        assert!(matches!(
+            err,
+            super::Error::WithLocation { ref source, .. }
+                if matches!(**source, super::Error::DeclarationsInGlobalBlock)
+        ));
+    }
+    #[test]
+    fn check_root_selectors() {
+        let style = r#"
+        :scope {
+            display: flex;
+        }
+        :host > .item {
+            color: red;
+        }
+        :host(.active) {
+            color: blue;
+        }
+        :host-context(.dark) .item {
+            color: white;
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let uniq_class = output.class_name();
+        let suffix = output.class_suffix();
+        let mut output_css = String::new();
+        output_css.push_str(&format!(r#".{uniq_class}{{display:flex}}"#));
+        output_css.push_str(&format!(r#".{uniq_class}>.item-{suffix}{{color:red}}"#));
+        output_css.push_str(&format!(r#".{uniq_class}.active-{suffix}{{color:#00f}}"#));
+        output_css.push_str(&format!(
+            r#":is(.dark .{uniq_class},.dark.{uniq_class}) .item-{suffix}{{color:#fff}}"#
+        ));
+        assert_eq!(output.css_data, output_css);
+        assert_eq!(
+            output.classes_list().collect::<Vec<_>>(),
+            vec!["active", "item"]
+        );
+    }
+    #[test]
+    fn check_pseudo_elements() {
+        use super::{CssProcessor, CssProcessorOptions, ScopingMode};
+        // (selector, expected selector with `{s}` for scope and `{c}` for renamed class `a`)
+        let matrix = [
+            ("a::before", "a{s}:before"),
+            ("a:hover::before", "a:hover{s}:before"),
+            ("a::before:hover", "a{s}:before:hover"),
+            (".a::before", "{c}:before"),
+            (".a:hover::after", "{c}:hover:after"),
+            ("input::placeholder", "input{s}::placeholder"),
+            ("input:hover::placeholder", "input:hover{s}::placeholder"),
+            ("::selection", "{s}::selection"),
+            ("p::selection", "p{s}::selection"),
+            (".a ::selection", "{c} {s}::selection"),
+            ("li::marker", "li{s}::marker"),
+            ("li:hover::marker", "li:hover{s}::marker"),
+            (".a li::marker", "{c} li{s}::marker"),
+            ("x-icon::part(label)", "x-icon{s}::part(label)"),
+            ("::slotted(span)", "{s}::slotted(span)"),
+            (":scope::before", "{s}:before"),
+        ];
+        for scoping in [ScopingMode::Class, ScopingMode::Attribute] {
+            let options = CssProcessorOptions::new().scoping(scoping);
+            for (selector, expected) in matrix {
+                let style = format!("{selector} {{ color: red; }}");
+                let output = CssProcessor::process_style_with_options(&style, &options).unwrap();
+                let scope = match output.scope_attr() {
+                    Some(attr) => format!("[{attr}]"),
+                    None => format!(".{}", output.class_name()),
+                };
+                let class = output
+                    .classes_map()
+                    .get("a")
+                    .map(|a| format!(".{}", a.class_name))
+                    .unwrap_or_default();
+                let expected = expected.replace("{s}", &scope).replace("{c}", &class);
+                assert_eq!(
+                    output.css_data,
+                    format!("{expected}{{color:red}}"),
+                    "{selector}"
+                );
+            }
+        }
+    }
+    #[test]
+    fn check_not_selector() {
+        let style = r#"
+        .item:not(.active) {
+            color: red;
+        }
+        li:not(.active, div) {
+            color: blue;
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let uniq_class = output.class_name();
+        let suffix = output.class_suffix();
+        let mut output_css = String::new();
+        output_css.push_str(&format!(
+            r#".item-{suffix}:not(.active-{suffix}){{color:red}}"#
+        ));
+        // Negated class doesn't scope element, so scope class is still appended.
+        output_css.push_str(&format!(
+            r#"li:not(.active-{suffix},div).{uniq_class}{{color:#00f}}"#
+        ));
+        assert_eq!(output.css_data, output_css);
+        assert_eq!(
+            output.classes_list().collect::<Vec<_>>(),
+            vec!["active", "item"]
+        );
+    }
+    #[test]
+    fn check_is_selector() {
+        let style = r#"
+        :is(.a, .b) {
+            color: red;
+        }
+        :is(.a, div) span {
+            color: blue;
+        }
+        :is(section .a) {
+            color: white;
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let uniq_class = output.class_name();
+        let suffix = output.class_suffix();
+        let mut output_css = String::new();
+        output_css.push_str(&format!(r#":is(.a-{suffix},.b-{suffix}){{color:red}}"#));
+        output_css.push_str(&format!(
+            r#":is(.a-{suffix},div).{uniq_class} span.{uniq_class}{{color:#00f}}"#
+        ));
+        output_css.push_str(&format!(
+            r#":is(section.{uniq_class} .a-{suffix}){{color:#fff}}"#
+        ));
+        assert_eq!(output.css_data, output_css);
+        assert_eq!(output.classes_list().collect::<Vec<_>>(), vec!["a", "b"]);
+    }
+    #[test]
+    fn check_where_selector() {
+        let style = r#"
+        :where(.a) p {
+            color: red;
+        }
+        :where(:global(.dark), .light) .b {
+            color: blue;
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let uniq_class = output.class_name();
+        let suffix = output.class_suffix();
+        let mut output_css = String::new();
+        output_css.push_str(&format!(
+            r#":where(.a-{suffix}) p.{uniq_class}{{color:red}}"#
+        ));
+        output_css.push_str(&format!(
+            r#":where(.dark,.light-{suffix}) .b-{suffix}{{color:#00f}}"#
+        ));
+        assert_eq!(output.css_data, output_css);
+        assert_eq!(
+            output.classes_list().collect::<Vec<_>>(),
+            vec!["a", "b", "light"]
+        );
+    }
+    #[test]
+    fn check_has_selector() {
+        let style = r#"
+        .card:has(> .icon) {
+            color: red;
+        }
+        li:has(+ li, img) {
+            color: blue;
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let uniq_class = output.class_name();
+        let suffix = output.class_suffix();
+        let mut output_css = String::new();
+        output_css.push_str(&format!(
+            r#".card-{suffix}:has(>.icon-{suffix}){{color:red}}"#
+        ));
+        // Elements in `:has(..)` belong to component too, so they are scoped.
+        output_css.push_str(&format!(
+            r#"li:has(+li.{uniq_class},img.{uniq_class}).{uniq_class}{{color:#00f}}"#
+        ));
+        assert_eq!(output.css_data, output_css);
+        assert_eq!(
+            output.classes_list().collect::<Vec<_>>(),
+            vec!["card", "icon"]
+        );
+    }
+    #[test]
+    fn check_nth_of_selector() {
+        let style = r#"
+        :nth-child(2n of .item) {
+            color: red;
+        }
+        "#;
+        let output = super::CssProcessor::process_style(style).unwrap();
+        let suffix = output.class_suffix();
+        assert_eq!(
+            output.css_data,
+            format!(r#":nth-child(2n of .item-{suffix}){{color:red}}"#)
+        );
+        assert_eq!(output.classes_list().collect::<Vec<_>>(), vec!["item"]);
+    }
+    #[test]
     fn check_deep_selector() {
         let style = r#"
         :deep(.my-class) {
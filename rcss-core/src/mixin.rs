@@ -0,0 +1,145 @@
+//! Support of mixins.
+//!
+//! Mixin is declared in style with declared struct: `@rcss(mixin name) { color: red; }`,
+//! and is exported as associated const of this struct.
+//! Other styles can include its declarations with `@include path::to::Struct::name;`.
+//!
+//! Declarations of mixin are known only when mixin is resolved by rust (or bundler),
+//! so each `@include` is replaced with placeholder declaration that keeps its position in rule,
+//! and later is handled in the same way as interpolated constant.
+
+use std::borrow::Cow;
+
+use crate::source_map::TextEdits;
+
+/// Name of property that is used in processed css instead of `@include` directive.
+pub fn include_placeholder(id: usize) -> String {
+    format!("__RCSS__MIXIN_{id}__")
+}
+
+/// Declaration that is placed instead of `@include` directive, in the form it is printed by lightningcss.
+pub fn include_declaration(id: usize, pretty: bool) -> String {
+    let separator = if pretty { ": " } else { ":" };
+    format!("{}{separator}include", include_placeholder(id))
+}
+
+const INCLUDE: &str = "@include";
+
+/// Returns byte offset of the first `@include` at position of declaration (after `{`, `;` or `}`).
+/// Strings and comments are skipped, since they can contain `@include` text.
+fn find_include(source: &str) -> Option<usize> {
+    let mut declaration_start = true;
+    let mut chars = source.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                // skip string
+                while let Some((_, next)) = chars.next() {
+                    match next {
+                        '\\' => {
+                            chars.next();
+                        }
+                        next if next == c => break,
+                        _ => {}
+                    }
+                }
+                declaration_start = false;
+            }
+            '/' if chars.next_if(|(_, c)| *c == '*').is_some() => {
+                // skip comment
+                let mut prev = None;
+                for (_, next) in chars.by_ref() {
+                    if prev == Some('*') && next == '/' {
+                        break;
+                    }
+                    prev = Some(next);
+                }
+            }
+            '@' if declaration_start && source[idx..].starts_with(INCLUDE) => return Some(idx),
+            '{' | ';' | '}' => declaration_start = true,
+            c if c.is_whitespace() => {}
+            _ => declaration_start = false,
+        }
+    }
+    None
+}
+
+/// Find `@include path;` directives in source and replace them with placeholder declarations.
+/// Returns paths of included mixins, in order of their appearance, source with replaced directives,
+/// and edits that map it back to original source.
+pub fn handle_includes(mut source: &str) -> (Vec<String>, Cow<'_, str>, TextEdits) {
+    let mut paths = vec![];
+    let mut edits = TextEdits::new();
+    let mut result = String::new();
+    // Offset of `source` in original string.
+    let mut offset = 0;
+    while let Some(start) = find_include(source) {
+        let rest = &source[start + INCLUDE.len()..];
+        // Ignore at-rules that only starts with `include` (like `@included`).
+        if !rest.starts_with(char::is_whitespace) {
+            result.push_str(&source[..start + INCLUDE.len()]);
+            source = rest;
+            offset += start + INCLUDE.len();
+            continue;
+        }
+        // Semicolon can be omitted for last declaration in block.
+        let end = rest.find([';', '}']).unwrap_or(rest.len());
+        let path = rest[..end].trim();
+
+        result.push_str(&source[..start]);
+        let processed_start = result.len();
+        result.push_str(&include_declaration(paths.len(), false));
+        let directive_end = start + INCLUDE.len() + end;
+        edits.push(
+            offset + start..offset + directive_end,
+            processed_start..result.len(),
+        );
+        paths.push(path.to_owned());
+        source = &rest[end..];
+        offset += directive_end;
+    }
+    if paths.is_empty() {
+        return (paths, Cow::Borrowed(source), edits);
+    }
+    result.push_str(source);
+    (paths, result.into(), edits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_handle_includes() {
+        let source = ".a { @include crate::Base::focus; color: red; @include Base::reset }";
+        let (paths, result, edits) = handle_includes(source);
+        assert_eq!(paths, vec!["crate::Base::focus", "Base::reset"]);
+        assert_eq!(
+            result,
+            ".a { __RCSS__MIXIN_0__:include; color: red; __RCSS__MIXIN_1__:include}"
+        );
+        let color = result.find("color").unwrap();
+        assert_eq!(edits.original_offset(color), source.find("color").unwrap());
+
+        let source = ".a { color: red }";
+        let (paths, result, _) = handle_includes(source);
+        assert!(paths.is_empty());
+        assert!(matches!(result, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_include_in_string_and_comment() {
+        let source = r#".a::before { content: "@include tips"; color: red; }"#;
+        let (paths, result, _) = handle_includes(source);
+        assert!(paths.is_empty());
+        assert_eq!(result, source);
+
+        let source = ".a { /* @include Base::focus; */ color: red; @include Base::reset; }";
+        let (paths, result, _) = handle_includes(source);
+        assert_eq!(paths, vec!["Base::reset"]);
+        assert_eq!(
+            result,
+            ".a { /* @include Base::focus; */ color: red; __RCSS__MIXIN_0__:include; }"
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Strategies of generating scoped names for classes, `@keyframes` and custom properties.
+//!
+//! - `suffix` (default) - `class-XXXX`, where `XXXX` is part of scope id;
+//! - `readable` - `module_path__Struct__class-XXXX`, useful for debugging;
+//! - `minified` - short hashed names (like `aB3dE`), to reduce size of styles in production.
+
+use std::{path::Path, str::FromStr};
+
+use crate::Error;
+
+/// Strategy of generating scoped names.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NamingStrategy {
+    #[default]
+    Suffix,
+    Readable,
+    Minified,
+}
+
+impl FromStr for NamingStrategy {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "suffix" => Ok(Self::Suffix),
+            "readable" => Ok(Self::Readable),
+            "minified" => Ok(Self::Minified),
+            _ => Err(Error::UnknownNamingStrategy(s.to_owned())),
+        }
+    }
+}
+
+/// Information about style location, that is used to generate scoped names.
+#[derive(Clone, Debug, Default)]
+pub struct Naming {
+    pub strategy: NamingStrategy,
+    /// Module path of style (see [`module_path_from_file`]), used by readable strategy.
+    pub module_path: String,
+    /// Mixed into hash of scope id, so equal styles from different places get different ids.
+    pub salt: String,
+}
+
+/// Per crate naming configuration, from `[package.metadata.rcss]`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NamingConfig {
+    pub strategy: NamingStrategy,
+    /// Mix crate name and module path into scope id (`salt-scope-id = true`).
+    pub salt_scope_id: bool,
+}
+
+impl NamingConfig {
+    /// Returns naming of style declared in `file` (if known) of crate `crate_name`.
+    /// Macro and bundler should use it to produce same names.
+    pub fn naming(&self, crate_name: &str, file: Option<&Path>) -> Naming {
+        // Module path is also used by bundler as prefix of minified names mapping.
+        let module_path = file
+            .map(|file| module_path_from_file(crate_name, file))
+            .unwrap_or_else(|| crate_name.replace('-', "_"));
+        let salt = if self.salt_scope_id {
+            module_path.clone()
+        } else {
+            String::new()
+        };
+        Naming {
+            strategy: self.strategy,
+            module_path,
+            salt,
+        }
+    }
+}
+
+/// Returns module path of style (like `my_crate__theme__colors`) by crate name and path to source file.
+/// Inline modules are not included, because bundler and macro should produce the same path.
+pub fn module_path_from_file(crate_name: &str, file: &Path) -> String {
+    let components = file
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+    // Modules are counted from the last `src` directory.
+    let start = components
+        .iter()
+        .rposition(|c| c == "src")
+        .map(|i| i + 1)
+        .unwrap_or(components.len().saturating_sub(1));
+    let mut modules = components[start..]
+        .iter()
+        .map(|c| c.trim_end_matches(".rs").to_owned())
+        .collect::<Vec<_>>();
+    if matches!(
+        modules.last().map(String::as_str),
+        Some("lib" | "main" | "mod")
+    ) {
+        modules.pop();
+    }
+    std::iter::once(crate_name.replace('-', "_"))
+        .chain(modules)
+        .collect::<Vec<_>>()
+        .join("__")
+}
+
+/// Renames identifiers of single style, according to naming strategy.
+#[derive(Clone, Debug)]
+pub(crate) struct Renamer {
+    strategy: NamingStrategy,
+    // Module path and struct name, for readable names.
+    // Macro doesn't know inline modules, so suffix is kept to make names of structs with same name unique.
+    prefix: String,
+    declared: bool,
+    suffix: String,
+    // Scope id, used as seed for minified names.
+    scope_id: String,
+}
+
+impl Renamer {
+    pub fn new(
+        naming: &Naming,
+        scope_id: &str,
+        suffix: &str,
+        declared_struct: Option<&str>,
+    ) -> Self {
+        let prefix = [Some(naming.module_path.as_str()), declared_struct]
+            .into_iter()
+            .flatten()
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("__");
+        Self {
+            strategy: naming.strategy,
+            declared: declared_struct.is_some(),
+            prefix,
+            suffix: suffix.to_owned(),
+            scope_id: scope_id.to_owned(),
+        }
+    }
+
+    /// Returns class that is added to selectors without classes.
+    pub fn scope_class(&self, scope_id: &str) -> String {
+        match self.strategy {
+            NamingStrategy::Readable if self.declared && !self.prefix.is_empty() => {
+                format!("{}-{}", self.prefix, self.suffix)
+            }
+            _ => scope_id.to_owned(),
+        }
+    }
+
+    /// Returns scoped name of class, keyframes or custom property (with leading `--`).
+    pub fn rename(&self, name: &str) -> String {
+        let (dashes, name) = match name.strip_prefix("--") {
+            Some(name) => ("--", name),
+            None => ("", name),
+        };
+        let suffix = &self.suffix;
+        match self.strategy {
+            NamingStrategy::Readable if self.prefix.is_empty() => {
+                format!("{dashes}{name}-{suffix}")
+            }
+            NamingStrategy::Readable => format!("{dashes}{}__{name}-{suffix}", self.prefix),
+            NamingStrategy::Minified => format!("{dashes}{}", minified_name(&self.scope_id, name)),
+            NamingStrategy::Suffix => format!("{dashes}{name}-{suffix}"),
+        }
+    }
+}
+
+// Short deterministic name, that starts with letter, to be valid css identifier.
+fn minified_name(scope_id: &str, name: &str) -> String {
+    const FIRST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    const REST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    // FNV-1a
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in scope_id.bytes().chain([b':']).chain(name.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    let mut result = String::new();
+    result.push(FIRST[(hash % FIRST.len() as u64) as usize] as char);
+    hash /= FIRST.len() as u64;
+    for _ in 0..4 {
+        result.push(REST[(hash % REST.len() as u64) as usize] as char);
+        hash /= REST.len() as u64;
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn module_path() {
+        let path = |file: &str| module_path_from_file("my-crate", Path::new(file));
+        assert_eq!(path("/home/user/my-crate/src/lib.rs"), "my_crate");
+        assert_eq!(path("src/theme/mod.rs"), "my_crate__theme");
+        assert_eq!(
+            path("/project/src/theme/colors.rs"),
+            "my_crate__theme__colors"
+        );
+        assert_eq!(path("src/bin/app.rs"), "my_crate__bin__app");
+    }
+
+    #[test]
+    fn rename_strategies() {
+        let naming = |strategy| Naming {
+            strategy,
+            module_path: "my_crate__theme".into(),
+            ..Default::default()
+        };
+        let renamer = Renamer::new(&naming(NamingStrategy::Suffix), "_Abcdef", "Abcd", None);
+        assert_eq!(renamer.rename("button"), "button-Abcd");
+        assert_eq!(renamer.rename("--accent"), "--accent-Abcd");
+
+        let declared = Some("Button");
+        let renamer = Renamer::new(
+            &naming(NamingStrategy::Readable),
+            "_Abcdef",
+            "Abcd",
+            declared,
+        );
+        assert_eq!(
+            renamer.rename("primary"),
+            "my_crate__theme__Button__primary-Abcd"
+        );
+        assert_eq!(
+            renamer.rename("--accent"),
+            "--my_crate__theme__Button__accent-Abcd"
+        );
+        assert_eq!(
+            renamer.scope_class("_Abcdef"),
+            "my_crate__theme__Button-Abcd"
+        );
+        let renamer = Renamer::new(&naming(NamingStrategy::Readable), "_Abcdef", "Abcd", None);
+        assert_eq!(renamer.rename("primary"), "my_crate__theme__primary-Abcd");
+
+        let renamer = Renamer::new(&naming(NamingStrategy::Minified), "_Abcdef", "Abcd", None);
+        let name = renamer.rename("primary");
+        assert_eq!(name.len(), 5);
+        assert!(name.starts_with(|c: char| c.is_ascii_alphabetic()));
+        assert_eq!(name, renamer.rename("primary"));
+        assert_ne!(name, renamer.rename("secondary"));
+        assert!(renamer.rename("--accent").starts_with("--"));
+    }
+}
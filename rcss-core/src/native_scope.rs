@@ -0,0 +1,154 @@
+//! Support of native `@scope` rule (see [`ScopingMode::Scope`](crate::ScopingMode::Scope)).
+//!
+//! Style rules are wrapped in `@scope (.scope-class) to (..limits) { .. }`, instead of renaming classes.
+//! Limits are taken from `:deep(..)` selectors: in `.slot :deep(.item)`, content of `.slot` belongs to other component,
+//! so rules of style stop at `.slot > *`, while rules with `:deep` are wrapped in `@scope` without limits.
+
+use lightningcss::{
+    rules::{scope::ScopeRule, CssRule, CssRuleList, Location},
+    selector::{Component, Selector, SelectorList},
+    targets::Browsers,
+    traits::IntoOwned,
+};
+
+/// Kind of top level rule, collected by [`SelectorVisitor`](crate::visitor::SelectorVisitor).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ScopeGroup {
+    /// Rule from `@global` block, kept outside of `@scope`.
+    Unscoped,
+    /// Rule is limited by `:deep` boundaries.
+    Scoped,
+    /// Rule uses `:deep(..)`, so it should reach content of other components.
+    Deep,
+}
+
+// Encode browser version in the same way as lightningcss does.
+const fn version(major: u32, minor: u32) -> u32 {
+    (major << 16) | (minor << 8)
+}
+
+/// Check if all target browsers support `@scope` rule.
+/// Without targets, style is expected to be used in modern browsers.
+pub(crate) fn is_supported(browsers: Option<Browsers>) -> bool {
+    let Some(browsers) = browsers else {
+        return true;
+    };
+    let supported = |target: Option<u32>, min: u32| target.is_none_or(|target| target >= min);
+    supported(browsers.chrome, version(118, 0))
+        && supported(browsers.edge, version(118, 0))
+        && supported(browsers.android, version(118, 0))
+        && supported(browsers.opera, version(104, 0))
+        && supported(browsers.samsung, version(25, 0))
+        && supported(browsers.safari, version(17, 4))
+        && supported(browsers.ios_saf, version(17, 4))
+        && supported(browsers.firefox, version(146, 0))
+        && browsers.ie.is_none()
+}
+
+// Only rules that contain style rules can be nested in `@scope`.
+fn can_be_scoped<R>(rule: &CssRule<'_, R>) -> bool {
+    matches!(
+        rule,
+        CssRule::Style(_)
+            | CssRule::Media(_)
+            | CssRule::Supports(_)
+            | CssRule::Container(_)
+            | CssRule::LayerBlock(_)
+            | CssRule::StartingStyle(_)
+            | CssRule::Nesting(_)
+    )
+}
+
+/// Wrap top level rules in `@scope` rules, keeping their order.
+/// `groups` are kinds of rules, in the same order as rules.
+pub(crate) fn wrap_rules<'i, R>(
+    rules: &mut CssRuleList<'i, R>,
+    groups: &[ScopeGroup],
+    root: &str,
+    limits: &[Selector<'static>],
+) {
+    let scope_start = SelectorList::from(Component::Class(root.to_owned().into()));
+    let scope_end = (!limits.is_empty())
+        .then(|| SelectorList::new(limits.iter().cloned().map(IntoOwned::into_owned).collect()));
+    let wrap = |group: ScopeGroup, rules: Vec<CssRule<'i, R>>, loc: Location| {
+        CssRule::Scope(ScopeRule {
+            scope_start: Some(scope_start.clone()),
+            scope_end: (group == ScopeGroup::Scoped)
+                .then(|| scope_end.clone())
+                .flatten(),
+            rules: CssRuleList(rules),
+            loc,
+        })
+    };
+
+    let mut result = Vec::with_capacity(rules.0.len());
+    // Consecutive rules of the same group are wrapped in single `@scope`.
+    let mut run: Option<(ScopeGroup, Location, Vec<CssRule<'i, R>>)> = None;
+    for (rule, &group) in rules.0.drain(..).zip(groups) {
+        let group = if can_be_scoped(&rule) {
+            group
+        } else {
+            ScopeGroup::Unscoped
+        };
+        if run
+            .as_ref()
+            .is_some_and(|(run_group, ..)| *run_group != group)
+        {
+            let (run_group, loc, run_rules) = run.take().unwrap();
+            result.push(wrap(run_group, run_rules, loc));
+        }
+        if group == ScopeGroup::Unscoped {
+            result.push(rule);
+            continue;
+        }
+        let loc = rule_location(&rule);
+        run.get_or_insert_with(|| (group, loc, vec![])).2.push(rule);
+    }
+    if let Some((group, loc, rules)) = run {
+        result.push(wrap(group, rules, loc));
+    }
+    rules.0 = result;
+}
+
+fn rule_location<R>(rule: &CssRule<'_, R>) -> Location {
+    match rule {
+        CssRule::Style(rule) => rule.loc,
+        CssRule::Media(rule) => rule.loc,
+        CssRule::Supports(rule) => rule.loc,
+        CssRule::Container(rule) => rule.loc,
+        CssRule::LayerBlock(rule) => rule.loc,
+        CssRule::StartingStyle(rule) => rule.loc,
+        CssRule::Nesting(rule) => rule.loc,
+        _ => Location {
+            source_index: 0,
+            line: 0,
+            column: 1,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_scope_support() {
+        assert!(is_supported(None));
+        let modern = Browsers {
+            chrome: Some(version(120, 0)),
+            safari: Some(version(17, 4)),
+            ..Default::default()
+        };
+        assert!(is_supported(Some(modern)));
+        let old_safari = Browsers {
+            safari: Some(version(16, 0)),
+            ..modern
+        };
+        assert!(!is_supported(Some(old_safari)));
+        let ie = Browsers {
+            ie: Some(version(11, 0)),
+            ..modern
+        };
+        assert!(!is_supported(Some(ie)));
+    }
+}
@@ -0,0 +1,156 @@
+//! Options of style processing, see [`CssProcessor::process_style_with_options`](crate::CssProcessor::process_style_with_options).
+//!
+//! Macro and bundler should process style with the same options, to produce the same output.
+
+use std::str::FromStr;
+
+use lightningcss::targets::Browsers;
+
+use crate::{Error, Naming, SourceLocation};
+
+/// How style is isolated from other styles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScopingMode {
+    /// Classes and `@keyframes` (and custom properties with `@rcss(scope-vars)`) are renamed,
+    /// and selectors without class are scoped with scope class.
+    #[default]
+    Class,
+    /// Same as `Class`, but selectors without class are scoped with attribute (like `[data-rcss-sauj]`),
+    /// that should be set on each element of component (see [`CssOutput::scope_attr`](crate::CssOutput::scope_attr)).
+    Attribute,
+    /// Style is wrapped in native `@scope (.scope-class)` rule, and classes keep their names.
+    /// Names that `@scope` doesn't isolate (`@keyframes`, custom properties) are still renamed.
+    ///
+    /// Falls back to `Class` mode, if targets don't support `@scope`,
+    /// or style uses selectors that can't be expressed with it (inline `:global(..)`).
+    Scope,
+    /// Style is not scoped, same as if whole style was declared in `@global { .. }` block.
+    Global,
+}
+
+impl FromStr for ScopingMode {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "class" => Ok(Self::Class),
+            "attribute" => Ok(Self::Attribute),
+            "scope" => Ok(Self::Scope),
+            "global" => Ok(Self::Global),
+            _ => Err(Error::UnknownScopingMode(s.to_owned())),
+        }
+    }
+}
+
+/// Draft syntax, that lightningcss supports only when explicitly enabled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Drafts {
+    /// Always compile nested rules into flat ones, even if targets support nesting.
+    pub nesting: bool,
+    /// Parse `@custom-media` rules, and replace them in media queries.
+    pub custom_media: bool,
+}
+
+/// Options of style processing.
+///
+/// ```
+/// # use rcss_core::{CssProcessor, CssProcessorOptions};
+/// let options = CssProcessorOptions::new().pretty(true);
+/// let output = CssProcessor::process_style_with_options(".a { color: red }", &options).unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CssProcessorOptions {
+    pub(crate) browsers: Option<Browsers>,
+    pub(crate) naming: Naming,
+    pub(crate) location: Option<SourceLocation>,
+    pub(crate) literal: Option<String>,
+    pub(crate) minify: bool,
+    pub(crate) pretty: bool,
+    pub(crate) drafts: Drafts,
+    pub(crate) error_recovery: bool,
+    pub(crate) scoping: ScopingMode,
+}
+
+impl CssProcessorOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lower and prefix style for browser targets.
+    /// Targets can be created from browserslist query using [`browsers_from_query`](crate::browsers_from_query).
+    pub fn targets(mut self, browsers: Option<Browsers>) -> Self {
+        self.browsers = browsers;
+        self
+    }
+
+    /// Generate scoped names with provided naming strategy.
+    pub fn naming(mut self, naming: Naming) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// Generate source map, that points to location of style in rust source.
+    pub fn source_map(mut self, location: SourceLocation) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    /// Style is the value of rust string literal with provided source text (like `r#"..."#`),
+    /// so locations of errors and source map point to the literal, even if it contains escapes.
+    pub fn literal(mut self, literal: impl Into<String>) -> Self {
+        self.literal = Some(literal.into());
+        self
+    }
+
+    /// Optimize style (merge rules, shorten values, etc.).
+    /// Style is always optimized when targets are provided, since it is needed for lowering.
+    pub fn minify(mut self, minify: bool) -> Self {
+        self.minify = minify;
+        self
+    }
+
+    /// Print style with indentation and new lines, instead of compact form.
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Enable draft syntax.
+    pub fn drafts(mut self, drafts: Drafts) -> Self {
+        self.drafts = drafts;
+        self
+    }
+
+    /// Skip invalid rules and declarations instead of failing,
+    /// they are reported in [`CssOutput::warnings`](crate::CssOutput::warnings).
+    /// Invalid `@rcss(..)` rules are still reported as errors.
+    pub fn error_recovery(mut self, error_recovery: bool) -> Self {
+        self.error_recovery = error_recovery;
+        self
+    }
+
+    /// Change how style is isolated from other styles.
+    pub fn scoping(mut self, scoping: ScopingMode) -> Self {
+        self.scoping = scoping;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_scoping_mode() {
+        assert_eq!("class".parse::<ScopingMode>().unwrap(), ScopingMode::Class);
+        assert_eq!(
+            "global".parse::<ScopingMode>().unwrap(),
+            ScopingMode::Global
+        );
+        assert_eq!(
+            "attribute".parse::<ScopingMode>().unwrap(),
+            ScopingMode::Attribute
+        );
+        assert_eq!("scope".parse::<ScopingMode>().unwrap(), ScopingMode::Scope);
+        assert!("unknown".parse::<ScopingMode>().is_err());
+    }
+}
@@ -7,6 +7,8 @@
 use std::{fmt::Debug, str::FromStr};
 
 use lightningcss::{
+    declaration::DeclarationBlock,
+    rules::CssRuleList,
     traits::AtRuleParser,
     visitor::{Visit, VisitTypes, Visitor},
 };
@@ -15,39 +17,106 @@ use quote::{ToTokens, TokenStreamExt};
 use thiserror::Error;
 
 use syn::{ItemStruct, Path, Token};
-pub struct RcssAtRuleParser;
+
+#[derive(Default)]
+pub struct RcssAtRuleParser {
+    declared: bool,
+    extended: bool,
+    mixins: Vec<String>,
+    /// Lightningcss replaces errors of custom at-rules with generic one,
+    /// so original error is kept here, with location of the rule arguments.
+    pub error: Option<(AtRuleError, cssparser::SourceLocation)>,
+}
 
 #[derive(Clone)]
-pub enum RcssAtRuleConfig {
-    Struct(ItemStruct),
-    Extend(Path),
+pub enum RcssAtRuleConfig<'i> {
+    Struct(Box<ItemStruct>),
+    /// Extend existing style, `new_classes` is set by `@rcss(extend path with new-classes)`.
+    Extend {
+        path: Path,
+        new_classes: bool,
+    },
+    /// Rename custom properties declared in style, to avoid conflicts with other styles.
+    ScopeVars,
+    /// Rename ids used in selectors, in the same way as classes.
+    ScopeIds,
+    /// `@global { ... }` block, rules inside it are neither scoped nor renamed.
+    Global(CssRuleList<'i, RcssAtRuleConfig<'i>>),
+    /// `@rcss(mixin name) { ... }` block, declarations that can be included in other styles.
+    Mixin {
+        name: syn::Ident,
+        declarations: DeclarationBlock<'i>,
+    },
 }
-impl Debug for RcssAtRuleConfig {
+impl Debug for RcssAtRuleConfig<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             RcssAtRuleConfig::Struct(item_mod) => write!(f, "Mod{}", item_mod.to_token_stream()),
-            RcssAtRuleConfig::Extend(path) => write!(f, "Extend{}", path.to_token_stream()),
+            RcssAtRuleConfig::Extend { path, new_classes } => {
+                write!(f, "Extend{}{new_classes}", path.to_token_stream())
+            }
+            RcssAtRuleConfig::ScopeVars => write!(f, "ScopeVars"),
+            RcssAtRuleConfig::ScopeIds => write!(f, "ScopeIds"),
+            RcssAtRuleConfig::Global(rules) => write!(f, "Global{rules:?}"),
+            RcssAtRuleConfig::Mixin { name, declarations } => {
+                write!(f, "Mixin{name}{declarations:?}")
+            }
         }
     }
 }
-impl RcssAtRuleConfig {
+impl RcssAtRuleConfig<'_> {
     pub fn from_token_stream(tokens: TokenStream) -> Result<Self, AtRuleError> {
         let mut iter = tokens.clone().into_iter();
 
-        if matches!(iter.next(), Some(TokenTree::Ident(i)) if i.to_string() == "extend") {
-            let tokens = iter.collect();
-            let result = syn::parse2::<Path>(tokens)?;
-            Ok(RcssAtRuleConfig::Extend(result))
+        match &*tokens.to_string().replace(' ', "") {
+            "scope-vars" => return Ok(RcssAtRuleConfig::ScopeVars),
+            "scope-ids" => return Ok(RcssAtRuleConfig::ScopeIds),
+            _ => {}
+        }
+        let first = iter.next();
+        if matches!(&first, Some(TokenTree::Ident(i)) if i == "mixin") {
+            // Mixin is exported as associated const, so its name should be valid rust identifier.
+            let name = syn::parse2::<syn::Ident>(iter.collect())
+                .map_err(|_| AtRuleError::UnknownRule(tokens.to_string()))?;
+            return Ok(RcssAtRuleConfig::Mixin {
+                name,
+                declarations: DeclarationBlock::new(),
+            });
+        }
+        if matches!(&first, Some(TokenTree::Ident(i)) if i == "extend") {
+            let path = iter
+                .by_ref()
+                .take_while(|tt| !matches!(tt, TokenTree::Ident(i) if i == "with"))
+                .collect();
+            let path = syn::parse2::<Path>(path)?;
+            let new_classes = match iter.collect::<TokenStream>().to_string().replace(' ', "") {
+                options if options.is_empty() => false,
+                options if options == "new-classes" => true,
+                _ => return Err(AtRuleError::UnknownRule(tokens.to_string())),
+            };
+            Ok(RcssAtRuleConfig::Extend { path, new_classes })
         } else {
+            let is_struct = tokens
+                .clone()
+                .into_iter()
+                .any(|tt| matches!(tt, TokenTree::Ident(i) if i == "struct"));
+            if !is_struct {
+                return Err(AtRuleError::UnknownRule(tokens.to_string()));
+            }
             let mut tokens = tokens;
-            // append semicolon, to statisfy syn::parse2::<ItemStruct>
-            Token![;](proc_macro2::Span::call_site()).to_tokens(&mut tokens);
+            // append semicolon to unit struct, to statisfy syn::parse2::<ItemStruct>
+            if !matches!(tokens.clone().into_iter().last(), Some(TokenTree::Group(g)) if g.delimiter() == proc_macro2::Delimiter::Brace)
+            {
+                Token![;](proc_macro2::Span::call_site()).to_tokens(&mut tokens);
+            }
 
             let mut result = syn::parse2::<ItemStruct>(tokens)?;
-            // TODO: Check instead of adding.
-            result.fields = syn::Fields::Unit;
+            // Fields are generated from classes of style.
+            if !matches!(result.fields, syn::Fields::Unit) {
+                return Err(AtRuleError::StructWithFields(result.ident.to_string()));
+            }
             result.semi_token = None;
-            Ok(RcssAtRuleConfig::Struct(result))
+            Ok(RcssAtRuleConfig::Struct(Box::new(result)))
         }
     }
 }
@@ -58,6 +127,26 @@ pub enum AtRuleError {
     UnexpectedAtRule,
     #[error("Rcss rule has no block")]
     UnexpectedBlock,
+    #[error("Invalid rcss rule")]
+    InvalidRule,
+    #[error("Failed to parse rules inside @global block: {0}")]
+    InvalidGlobalBlock(String),
+    #[error(
+        "Unknown rcss rule `{0}`, expected struct declaration, `extend path`, `mixin name`, `scope-vars` or `scope-ids`"
+    )]
+    UnknownRule(String),
+    #[error(
+        "Struct `{0}` declared with @rcss rule can't have fields, they are generated from style"
+    )]
+    StructWithFields(String),
+    #[error("Style can declare only one struct with @rcss rule")]
+    DuplicateDeclare,
+    #[error("Style can extend only one other style with @rcss rule")]
+    DuplicateExtend,
+    #[error("Mixin `{0}` is declared more than once")]
+    DuplicateMixin(String),
+    #[error("Failed to parse declarations of mixin: {0}")]
+    InvalidMixinBlock(String),
     #[error("Failed to parse rcss rule as syn expression")]
     ErrorFromSyn(#[from] syn::Error),
     #[error("Failed to parse rcss rule as rust code")]
@@ -65,8 +154,8 @@ pub enum AtRuleError {
 }
 
 impl<'i> AtRuleParser<'i> for RcssAtRuleParser {
-    type Prelude = RcssAtRuleConfig;
-    type AtRule = RcssAtRuleConfig;
+    type Prelude = RcssAtRuleConfig<'i>;
+    type AtRule = RcssAtRuleConfig<'i>;
     type Error = AtRuleError;
 
     fn parse_prelude<'t>(
@@ -75,9 +164,14 @@ impl<'i> AtRuleParser<'i> for RcssAtRuleParser {
         input: &mut cssparser::Parser<'i, 't>,
         _options: &lightningcss::stylesheet::ParserOptions<'_, 'i>,
     ) -> Result<Self::Prelude, cssparser::ParseError<'i, Self::Error>> {
+        if name == "global" {
+            input.expect_exhausted()?;
+            return Ok(RcssAtRuleConfig::Global(CssRuleList(vec![])));
+        }
         if name != "rcss" {
             return Err(input.new_custom_error(AtRuleError::UnexpectedAtRule));
         }
+        let location = input.current_source_location();
         input.expect_parenthesis_block()?;
         let stream = input.parse_nested_block(|input| {
             let start = input.state().position();
@@ -90,21 +184,61 @@ impl<'i> AtRuleParser<'i> for RcssAtRuleParser {
 
         let stream = stream.trim();
 
-        let tokens =
-            proc_macro2::TokenStream::from_str(stream).map_err(|e| input.new_custom_error(e))?;
-
-        RcssAtRuleConfig::from_token_stream(tokens).map_err(|e| input.new_custom_error(e))
+        let rule = proc_macro2::TokenStream::from_str(stream)
+            .map_err(AtRuleError::from)
+            .and_then(RcssAtRuleConfig::from_token_stream);
+        let rule = match rule {
+            Ok(RcssAtRuleConfig::Struct(_)) if self.declared => Err(AtRuleError::DuplicateDeclare),
+            Ok(RcssAtRuleConfig::Extend { .. }) if self.extended => {
+                Err(AtRuleError::DuplicateExtend)
+            }
+            Ok(RcssAtRuleConfig::Mixin { name, .. }) if self.mixins.contains(&name.to_string()) => {
+                Err(AtRuleError::DuplicateMixin(name.to_string()))
+            }
+            rule => rule,
+        };
+        match rule {
+            Ok(rule) => {
+                self.declared |= matches!(rule, RcssAtRuleConfig::Struct(_));
+                self.extended |= matches!(rule, RcssAtRuleConfig::Extend { .. });
+                if let RcssAtRuleConfig::Mixin { name, .. } = &rule {
+                    self.mixins.push(name.to_string());
+                }
+                Ok(rule)
+            }
+            Err(e) => {
+                let error = input.new_custom_error(AtRuleError::InvalidRule);
+                self.error = Some((e, location));
+                Err(error)
+            }
+        }
     }
 
     fn parse_block<'t>(
         &mut self,
-        _prelude: Self::Prelude,
+        prelude: Self::Prelude,
         _start: &cssparser::ParserState,
         input: &mut cssparser::Parser<'i, 't>,
-        _options: &lightningcss::stylesheet::ParserOptions<'_, 'i>,
-        _is_nested: bool,
+        options: &lightningcss::stylesheet::ParserOptions<'_, 'i>,
+        is_nested: bool,
     ) -> Result<Self::AtRule, cssparser::ParseError<'i, Self::Error>> {
-        Err(input.new_custom_error(AtRuleError::UnexpectedBlock))
+        match prelude {
+            RcssAtRuleConfig::Global(_) => {
+                // Inside of style rule, nested rules are relative to parent, same as in regular nesting.
+                let rules = if is_nested {
+                    CssRuleList::parse_style_block_with(input, options, self, true)
+                } else {
+                    CssRuleList::parse_with(input, options, self)
+                };
+                rules.map(RcssAtRuleConfig::Global).map_err(|e| {
+                    input.new_custom_error(AtRuleError::InvalidGlobalBlock(e.to_string()))
+                })
+            }
+            RcssAtRuleConfig::Mixin { name, .. } => DeclarationBlock::parse(input, options)
+                .map(|declarations| RcssAtRuleConfig::Mixin { name, declarations })
+                .map_err(|e| input.new_custom_error(AtRuleError::InvalidMixinBlock(e.to_string()))),
+            _ => Err(input.new_custom_error(AtRuleError::UnexpectedBlock)),
+        }
     }
 
     fn rule_without_block(
@@ -114,11 +248,14 @@ impl<'i> AtRuleParser<'i> for RcssAtRuleParser {
         _options: &lightningcss::stylesheet::ParserOptions<'_, 'i>,
         _is_nested: bool,
     ) -> Result<Self::AtRule, ()> {
-        Ok(prelude)
+        match prelude {
+            RcssAtRuleConfig::Global(_) | RcssAtRuleConfig::Mixin { .. } => Err(()),
+            prelude => Ok(prelude),
+        }
     }
 }
 
-impl lightningcss::traits::ToCss for RcssAtRuleConfig {
+impl lightningcss::traits::ToCss for RcssAtRuleConfig<'_> {
     fn to_css<W>(
         &self,
         dest: &mut lightningcss::printer::Printer<W>,
@@ -136,16 +273,39 @@ impl lightningcss::traits::ToCss for RcssAtRuleConfig {
                 item_mod.ident.to_tokens(&mut tokens);
                 tokens
             }
-            RcssAtRuleConfig::Extend(path) => path.to_token_stream(),
+            RcssAtRuleConfig::Extend { path, new_classes } => {
+                let mut tokens = path.to_token_stream();
+                if *new_classes {
+                    tokens.extend(quote::quote!(with new-classes));
+                }
+                tokens
+            }
+            RcssAtRuleConfig::ScopeVars => return dest.write_str("@rcss(scope-vars);"),
+            RcssAtRuleConfig::ScopeIds => return dest.write_str("@rcss(scope-ids);"),
+            RcssAtRuleConfig::Global(rules) => {
+                dest.write_str("@global{")?;
+                rules.to_css(dest)?;
+                return dest.write_str("}");
+            }
+            RcssAtRuleConfig::Mixin { name, declarations } => {
+                dest.write_str(&format!("@rcss(mixin {name}){{"))?;
+                declarations.to_css(dest)?;
+                return dest.write_str("}");
+            }
         };
         dest.write_str(&format!("@rcss({args});"))
     }
 }
 
-impl<'i, V: Visitor<'i, RcssAtRuleConfig>> Visit<'i, RcssAtRuleConfig, V> for RcssAtRuleConfig {
-    const CHILD_TYPES: VisitTypes = VisitTypes::empty();
-    fn visit_children(&mut self, _: &mut V) -> Result<(), V::Error> {
-        Ok(())
+impl<'i, V: Visitor<'i, RcssAtRuleConfig<'i>>> Visit<'i, RcssAtRuleConfig<'i>, V>
+    for RcssAtRuleConfig<'i>
+{
+    const CHILD_TYPES: VisitTypes = VisitTypes::all();
+    fn visit_children(&mut self, visitor: &mut V) -> Result<(), V::Error> {
+        match self {
+            RcssAtRuleConfig::Global(rules) => rules.visit(visitor),
+            _ => Ok(()),
+        }
     }
 }
 
@@ -170,7 +330,7 @@ mod tests {
         let stylesheet = lightningcss::stylesheet::StyleSheet::parse_with(
             input,
             Default::default(),
-            &mut RcssAtRuleParser,
+            &mut RcssAtRuleParser::default(),
         )
         .unwrap();
         let rule = stylesheet.rules.0.into_iter().next().unwrap();
@@ -201,12 +361,12 @@ mod tests {
         let stylesheet = lightningcss::stylesheet::StyleSheet::parse_with(
             input,
             Default::default(),
-            &mut RcssAtRuleParser,
+            &mut RcssAtRuleParser::default(),
         )
         .unwrap();
         let rule = stylesheet.rules.0.into_iter().next().unwrap();
         match &rule {
-            CssRule::Custom(super::RcssAtRuleConfig::Extend(path)) => {
+            CssRule::Custom(super::RcssAtRuleConfig::Extend { path, .. }) => {
                 assert_eq!(
                     path.to_token_stream().to_string(),
                     ":: path :: to :: my_mod"
@@ -217,4 +377,131 @@ mod tests {
         let output = rule.to_css_string(Default::default()).unwrap();
         assert_eq!(output, "@rcss(:: path :: to :: my_mod);");
     }
+
+    #[test]
+    fn check_at_rule_extend_new_classes() {
+        let tokens = "extend ::path::Foo with new-classes".parse().unwrap();
+        let rule = super::RcssAtRuleConfig::from_token_stream(tokens).unwrap();
+        assert!(matches!(
+            rule,
+            super::RcssAtRuleConfig::Extend {
+                new_classes: true,
+                ..
+            }
+        ));
+        let tokens = "extend ::path::Foo with something".parse().unwrap();
+        assert!(matches!(
+            super::RcssAtRuleConfig::from_token_stream(tokens),
+            Err(super::AtRuleError::UnknownRule(_))
+        ));
+    }
+
+    #[test]
+    fn check_at_rule_scope_vars() {
+        let input = r#"
+            @rcss(scope-vars);
+        "#;
+        let stylesheet = lightningcss::stylesheet::StyleSheet::parse_with(
+            input,
+            Default::default(),
+            &mut RcssAtRuleParser::default(),
+        )
+        .unwrap();
+        let rule = stylesheet.rules.0.into_iter().next().unwrap();
+        assert!(matches!(
+            rule,
+            CssRule::Custom(super::RcssAtRuleConfig::ScopeVars)
+        ));
+        let output = rule.to_css_string(Default::default()).unwrap();
+        assert_eq!(output, "@rcss(scope-vars);");
+    }
+
+    #[test]
+    fn check_at_rule_scope_ids() {
+        let input = r#"
+            @rcss(scope-ids);
+        "#;
+        let stylesheet = lightningcss::stylesheet::StyleSheet::parse_with(
+            input,
+            Default::default(),
+            &mut RcssAtRuleParser::default(),
+        )
+        .unwrap();
+        let rule = stylesheet.rules.0.into_iter().next().unwrap();
+        assert!(matches!(
+            rule,
+            CssRule::Custom(super::RcssAtRuleConfig::ScopeIds)
+        ));
+        let output = rule.to_css_string(Default::default()).unwrap();
+        assert_eq!(output, "@rcss(scope-ids);");
+    }
+
+    #[test]
+    fn check_at_rule_global() {
+        let input = r#"
+            @global {
+                .my-class {
+                    color: red;
+                }
+            }
+        "#;
+        let stylesheet = lightningcss::stylesheet::StyleSheet::parse_with(
+            input,
+            Default::default(),
+            &mut RcssAtRuleParser::default(),
+        )
+        .unwrap();
+        let rule = stylesheet.rules.0.into_iter().next().unwrap();
+        match &rule {
+            CssRule::Custom(super::RcssAtRuleConfig::Global(rules)) => assert_eq!(rules.0.len(), 1),
+            _ => unreachable!(),
+        }
+        let output = rule
+            .to_css_string(lightningcss::stylesheet::PrinterOptions {
+                minify: true,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(output, "@global{.my-class{color:red}}");
+    }
+
+    #[test]
+    fn check_at_rule_mixin() {
+        let input = r#"
+            @rcss(mixin focus_ring) {
+                outline: 2px solid red;
+                outline-offset: 2px;
+            }
+        "#;
+        let stylesheet = lightningcss::stylesheet::StyleSheet::parse_with(
+            input,
+            Default::default(),
+            &mut RcssAtRuleParser::default(),
+        )
+        .unwrap();
+        let rule = stylesheet.rules.0.into_iter().next().unwrap();
+        match &rule {
+            CssRule::Custom(super::RcssAtRuleConfig::Mixin { name, declarations }) => {
+                assert_eq!(name, "focus_ring");
+                assert_eq!(declarations.len(), 2);
+            }
+            _ => unreachable!(),
+        }
+        let output = rule
+            .to_css_string(lightningcss::stylesheet::PrinterOptions {
+                minify: true,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            output,
+            "@rcss(mixin focus_ring){outline:2px solid red;outline-offset:2px}"
+        );
+        // Mixin name should be valid rust identifier.
+        let tokens = "mixin focus-ring".parse().unwrap();
+        assert!(matches!(
+            super::RcssAtRuleConfig::from_token_stream(tokens),
+            Err(super::AtRuleError::UnknownRule(_))
+        ));
+    }
 }
@@ -0,0 +1,383 @@
+//! Source maps from generated css back to rust source files.
+//!
+//! Lightningcss reports positions relative to style text, they are shifted by position of style
+//! in rust file, so devtools can show where rule was declared.
+
+use std::ops::Range;
+
+use crate::{Error, Result};
+
+/// Parts of style text, that were replaced before parsing (like `${..}` interpolation or `@include`).
+/// Used to map positions, reported for processed text, back to the original text.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextEdits {
+    // Each stage is applied to text produced by previous one.
+    // Edits of stage are sorted: (range in processed text, range in original text).
+    stages: Vec<Vec<(Range<usize>, Range<usize>)>>,
+}
+
+impl TextEdits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `original` range of text was replaced with `processed` range.
+    /// Edits should be added in order of their position.
+    pub fn push(&mut self, original: Range<usize>, processed: Range<usize>) {
+        if self.stages.is_empty() {
+            self.stages.push(vec![]);
+        }
+        self.stages.last_mut().unwrap().push((processed, original));
+    }
+
+    /// Add edits that were applied to the text, produced by these edits.
+    pub fn then(mut self, next: TextEdits) -> Self {
+        self.stages.extend(next.stages);
+        self
+    }
+
+    /// Returns offset in original text, for offset in processed text.
+    /// Offsets inside of replaced part point to its start.
+    pub fn original_offset(&self, offset: usize) -> usize {
+        self.stages.iter().rev().fold(offset, |offset, edits| {
+            let Some((processed, original)) = edits
+                .iter()
+                .take_while(|(processed, _)| processed.start <= offset)
+                .last()
+            else {
+                return offset;
+            };
+            if offset < processed.end {
+                original.start
+            } else {
+                original.end + (offset - processed.end)
+            }
+        })
+    }
+
+    /// Returns position in original text, for position in processed text.
+    /// Lines and columns are counted from 0, columns are in UTF-16 code units (same as in lightningcss).
+    pub fn original_position(
+        &self,
+        processed: &str,
+        original: &str,
+        line: u32,
+        column: u32,
+    ) -> (u32, u32) {
+        let offset = offset_at(processed, line, column);
+        position_at(original, self.original_offset(offset))
+    }
+}
+
+/// Style text before and after preprocessing.
+pub(crate) struct EditedText<'a> {
+    pub original: &'a str,
+    pub processed: &'a str,
+    pub edits: &'a TextEdits,
+}
+
+impl EditedText<'_> {
+    /// Returns position in original text, for position in processed text.
+    pub fn original_position(&self, line: u32, column: u32) -> (u32, u32) {
+        self.edits
+            .original_position(self.processed, self.original, line, column)
+    }
+}
+
+/// Returns byte offset of position in text.
+/// Lines and columns are counted from 0, columns are in UTF-16 code units.
+pub fn offset_at(text: &str, line: u32, column: u32) -> usize {
+    let line_start = text
+        .split_inclusive('\n')
+        .take(line as usize)
+        .map(str::len)
+        .sum::<usize>();
+    let mut utf16_column = 0;
+    for (idx, c) in text[line_start..].char_indices() {
+        if utf16_column >= column || c == '\n' {
+            return line_start + idx;
+        }
+        utf16_column += c.len_utf16() as u32;
+    }
+    text.len()
+}
+
+/// Returns position of byte offset in text.
+/// Lines and columns are counted from 0, columns are in UTF-16 code units.
+pub fn position_at(text: &str, offset: usize) -> (u32, u32) {
+    let offset = offset.min(text.len());
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    let line = before.matches('\n').count() as u32;
+    let column = before[line_start..].encode_utf16().count() as u32;
+    (line, column)
+}
+
+/// Returns value of rust string literal (like `"..."` or `r#"..."#`),
+/// and edits that map positions in value back to the literal source text.
+/// Returns None if text is not a valid string literal.
+pub fn unescape_literal(literal: &str) -> Option<(String, TextEdits)> {
+    let raw_hashes = literal
+        .strip_prefix('r')
+        .map(|rest| rest.len() - rest.trim_start_matches('#').len());
+    let body_start = raw_hashes.map_or(0, |hashes| hashes + 1) + 1;
+    let closing = format!("\"{}", "#".repeat(raw_hashes.unwrap_or(0)));
+    if literal.get(body_start - 1..body_start) != Some("\"") {
+        return None;
+    }
+    let body_end = literal.rfind(&closing).filter(|end| *end >= body_start)?;
+    let body = &literal[body_start..body_end];
+
+    let mut edits = TextEdits::new();
+    // Opening quote (with raw prefix) is not part of value.
+    edits.push(0..body_start, 0..0);
+    let mut value = String::with_capacity(body.len());
+    let mut chars = body.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let value_start = value.len();
+        match c {
+            // Line ending is always `\n` in literal value.
+            '\r' if chars.next_if(|(_, c)| *c == '\n').is_some() => value.push('\n'),
+            '\\' if raw_hashes.is_none() => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                '0' => value.push('\0'),
+                c @ ('\\' | '\'' | '"') => value.push(c),
+                'x' => {
+                    let digits = [chars.next()?.1, chars.next()?.1];
+                    let code = u8::from_str_radix(&String::from_iter(digits), 16)
+                        .ok()
+                        .filter(u8::is_ascii)?;
+                    value.push(char::from(code));
+                }
+                'u' => {
+                    chars.next_if(|(_, c)| *c == '{')?;
+                    let mut digits = String::new();
+                    for (_, c) in chars.by_ref() {
+                        match c {
+                            '}' => break,
+                            '_' => {}
+                            c => digits.push(c),
+                        }
+                    }
+                    value.push(char::from_u32(u32::from_str_radix(&digits, 16).ok()?)?);
+                }
+                // Line continuation, skips line ending and leading whitespace of the next line.
+                '\n' | '\r' => while chars.next_if(|(_, c)| c.is_ascii_whitespace()).is_some() {},
+                _ => return None,
+            },
+            c => {
+                value.push(c);
+                continue;
+            }
+        }
+        let end = chars.peek().map_or(body.len(), |(end, _)| *end);
+        edits.push(
+            body_start + start..body_start + end,
+            value_start..value.len(),
+        );
+    }
+    Some((value, edits))
+}
+
+/// Position of the first character of style text in rust source file.
+/// Line and column are counted from 0.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// Mapping from position in generated css to position in rust source file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceMapping {
+    pub generated_line: u32,
+    pub generated_column: u32,
+    pub original_line: u32,
+    pub original_column: u32,
+}
+
+/// Source map of single style.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StyleSourceMap {
+    /// Rust source file of style.
+    pub file: String,
+    pub mappings: Vec<SourceMapping>,
+}
+
+impl StyleSourceMap {
+    pub(crate) fn from_printed(
+        map: &parcel_sourcemap::SourceMap,
+        location: &SourceLocation,
+        text: &EditedText,
+    ) -> Self {
+        let mappings = map
+            .get_mappings()
+            .into_iter()
+            .filter_map(|mapping| {
+                let original = mapping.original?;
+                // Printed positions are in preprocessed style, map them back to text of macro input.
+                let (line, column) =
+                    text.original_position(original.original_line, original.original_column);
+                // Only the first line of style is shifted by column of macro input.
+                let original_column = if line == 0 {
+                    location.column + column
+                } else {
+                    column
+                };
+                Some(SourceMapping {
+                    generated_line: mapping.generated_line,
+                    generated_column: mapping.generated_column,
+                    original_line: location.line + line,
+                    original_column,
+                })
+            })
+            .collect();
+        Self {
+            file: location.file.clone(),
+            mappings,
+        }
+    }
+
+    /// Returns source map of style, after parts of it were replaced.
+    /// `replacements` contains column of replaced part in original css, and change of its length.
+    /// Generated css is expected to be minified into single line.
+    pub fn with_replacements(&self, replacements: &[(u32, i64)]) -> Self {
+        let mappings = self
+            .mappings
+            .iter()
+            .map(|mapping| {
+                let shift: i64 = replacements
+                    .iter()
+                    .filter(|(column, _)| *column < mapping.generated_column)
+                    .map(|(_, delta)| delta)
+                    .sum();
+                SourceMapping {
+                    generated_column: (mapping.generated_column as i64 + shift) as u32,
+                    ..*mapping
+                }
+            })
+            .collect();
+        Self {
+            file: self.file.clone(),
+            mappings,
+        }
+    }
+}
+
+/// Replace all occurrences of `from` in single line css, and update its source map.
+pub(crate) fn replace_tracked(
+    css: &mut String,
+    source_map: Option<&mut StyleSourceMap>,
+    from: &str,
+    to: &str,
+) {
+    if let Some(source_map) = source_map {
+        let delta = to.len() as i64 - from.len() as i64;
+        let replacements = css
+            .match_indices(from)
+            .map(|(column, _)| (column as u32, delta))
+            .collect::<Vec<_>>();
+        *source_map = source_map.with_replacements(&replacements);
+    }
+    *css = css.replace(from, to);
+}
+
+/// Source map of css file, that combines multiple styles.
+pub struct SourceMapBuilder {
+    map: parcel_sourcemap::SourceMap,
+}
+
+impl SourceMapBuilder {
+    /// Paths to rust files are saved relative to `root` (usually directory of source map file).
+    pub fn new(root: &str) -> Self {
+        Self {
+            map: parcel_sourcemap::SourceMap::new(root),
+        }
+    }
+
+    /// Add source map of style, that starts at `line` and `column` of css file.
+    pub fn add(&mut self, style_map: &StyleSourceMap, line: u32, column: u32) {
+        let source = self.map.add_source(&style_map.file);
+        for mapping in &style_map.mappings {
+            let generated_column = if mapping.generated_line == 0 {
+                column + mapping.generated_column
+            } else {
+                mapping.generated_column
+            };
+            self.map.add_mapping(
+                line + mapping.generated_line,
+                generated_column,
+                Some(parcel_sourcemap::OriginalLocation::new(
+                    mapping.original_line,
+                    mapping.original_column,
+                    source,
+                    None,
+                )),
+            );
+        }
+    }
+
+    /// Returns source map in json format.
+    pub fn to_json(mut self) -> Result<String> {
+        self.map
+            .to_json(None)
+            .map_err(|e| Error::SourceMapError(format!("{e:?}")))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_text_edits() {
+        let original = "a { color: ${RED}; }\nb { @include X::m; }";
+        let processed = "a { color: __C0__; }\nb { __M0__:include; }";
+        let mut first = TextEdits::new();
+        first.push(11..17, 11..17);
+        let mut second = TextEdits::new();
+        second.push(25..38, 25..39);
+        let edits = first.then(second);
+        // Positions after replaced parts are shifted back.
+        assert_eq!(edits.original_offset(18), 18);
+        assert_eq!(edits.original_position(processed, original, 1, 19), (1, 18));
+        // Positions inside of replaced part point to its start.
+        assert_eq!(edits.original_position(processed, original, 1, 6), (1, 4));
+        assert_eq!(position_at("ab\ncd", 4), (1, 1));
+        assert_eq!(offset_at("ab\ncd", 1, 1), 4);
+        assert_eq!(offset_at("\u{1F600}a", 0, 2), 4);
+    }
+
+    #[test]
+    fn check_unescape_literal() {
+        let literal = r#""a\t\"b\u{2e}c \
+            d""#;
+        let (value, edits) = unescape_literal(literal).unwrap();
+        assert_eq!(value, "a\t\"b.c d");
+        // Offsets in value point to the same characters in literal.
+        for (offset, c) in value.char_indices().filter(|(_, c)| c.is_alphabetic()) {
+            assert_eq!(
+                literal[edits.original_offset(offset)..].chars().next(),
+                Some(c)
+            );
+        }
+        // Escapes are mapped to their start.
+        assert_eq!(
+            edits.original_offset(value.find('.').unwrap()),
+            literal.find("\\u").unwrap()
+        );
+
+        let literal = r##"r#"a "b" c"#"##;
+        let (value, edits) = unescape_literal(literal).unwrap();
+        assert_eq!(value, r#"a "b" c"#);
+        assert_eq!(
+            edits.original_offset(value.find('c').unwrap()),
+            literal.find('c').unwrap()
+        );
+        assert_eq!(unescape_literal("\"a\\q\""), None);
+        assert_eq!(unescape_literal("a"), None);
+    }
+}
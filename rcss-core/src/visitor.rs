@@ -1,35 +1,76 @@
 use std::collections::BTreeMap;
 
 use lightningcss::{
-    properties::custom::{TokenList, TokenOrValue},
-    rules::CssRule,
-    selector::{Component, PseudoClass, Selector},
+    error::ErrorLocation,
+    properties::{
+        animation::AnimationName,
+        custom::{CustomPropertyName, TokenList, TokenOrValue},
+        Property, PropertyId,
+    },
+    rules::{keyframes::KeyframesName, CssRule, CssRuleList, Location},
+    selector::{Combinator, Component, PseudoClass, Selector, SelectorList},
     stylesheet::{ParserOptions, PrinterOptions},
     traits::{ParseWithOptions, ToCss as _},
-    values::ident::Ident,
+    values::ident::{CustomIdent, DashedIdent, Ident},
     visit_types,
     visitor::{VisitTypes, Visitor},
 };
+use parcel_selectors::parser::NthOfSelectorData;
 use thiserror::Error;
 
-use crate::rcss_at_rule::RcssAtRuleConfig;
+use crate::{
+    global_names::{NameKind, ScopedNames},
+    native_scope::ScopeGroup,
+    rcss_at_rule::{AtRuleError, RcssAtRuleConfig},
+};
 
 pub(crate) struct SelectorVisitor {
     // Input:
     // Class name that should be appended to each selector without class.
     pub append_class: String,
+    // Name of attribute that is appended instead of class, if style uses attribute scoping.
+    pub scope_attr: Option<String>,
+    // Style is wrapped in native `@scope`, so selectors are left unscoped, and `:scope` is kept as is.
+    pub native_scope: bool,
     // Function that modify class_name to be unique.
     pub class_modify: Box<dyn FnMut(String) -> String>,
+    // Keyframes declared in style (original => scoped name).
+    // Used to rename @keyframes rules and animation references to them.
+    pub keyframes: BTreeMap<String, String>,
+    // Custom properties that should be renamed (original => scoped name).
+    // Empty, if style doesn't use `@rcss(scope-vars)`.
+    pub custom_properties: BTreeMap<String, String>,
+    // Layers, containers, counters, etc. declared in style (see `global_names` module).
+    pub scoped_names: ScopedNames,
+    // Rename ids in the same way as classes, if style uses `@rcss(scope-ids)`.
+    pub scope_ids: bool,
     // Output:
     // List of classes used in selectors.
     pub collect_classes: BTreeMap<String, String>,
+    // List of ids used in selectors (empty without `@rcss(scope-ids)`).
+    pub collect_ids: BTreeMap<String, String>,
+    // Classes composed into other classes with `composes:` (class => composed classes).
+    // Local classes are stored with original names.
+    pub composes: BTreeMap<String, Vec<ComposedClass>>,
     // If found macro should extend existing style from path.
     pub extend: Option<syn::Path>,
+    // Extension is allowed to add new classes.
+    pub extend_new_classes: bool,
     // If found macro should emit mod instead of inline struct.
     pub declare: Option<syn::ItemStruct>,
+    // Declarations of mixins, printed as css (name => declarations).
+    pub mixins: BTreeMap<String, String>,
+    // Kinds of top level rules, used to wrap them in `@scope` (see `native_scope` module).
+    pub scope_groups: Vec<ScopeGroup>,
+    // Boundaries of `:deep(..)` selectors, used as limits of `@scope`.
+    pub scope_limits: Vec<Selector<'static>>,
 
     // State:
     pub state: SelectorState,
+    // Depth of currently visited rule (1 for top level rules).
+    pub rule_depth: usize,
+    // Current top level rule uses `:deep(..)`.
+    pub deep_found: bool,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -37,6 +78,17 @@ pub struct SelectorState {
     class_found: bool,
     global_selector: bool,
     deep_selector: bool,
+    // Inside of style rule, nested selectors are relative to parent.
+    nested: bool,
+}
+impl SelectorState {
+    /// State of style, that is not scoped at all (see [`crate::ScopingMode::Global`]).
+    pub(crate) fn global() -> Self {
+        Self {
+            global_selector: true,
+            ..Default::default()
+        }
+    }
 }
 impl SelectorState {
     fn handle_class(&mut self) {
@@ -58,6 +110,101 @@ pub enum Error {
     GenericParser(#[from] GenericParseError),
     #[error("Not allowed token in selector list: {0}")]
     NotAllowedToken(String),
+    #[error("Failed to parse browserslist query: {0}")]
+    BrowserslistError(String),
+    #[error("Failed to minify css: {0}")]
+    MinifyFailed(String),
+    #[error("`composes` is only allowed in rule with single class selector")]
+    ComposesNotAllowed,
+    #[error("Invalid `composes` declaration: {0}")]
+    InvalidComposes(String),
+    #[error("Class `{0}` used in `composes` is not declared in style")]
+    UnknownComposedClass(String),
+    #[error("Unknown naming strategy `{0}`, expected one of: suffix, readable, minified")]
+    UnknownNamingStrategy(String),
+    #[error("Unknown scoping mode `{0}`, expected one of: class, attribute, scope, global")]
+    UnknownScopingMode(String),
+    #[error("Failed to generate source map: {0}")]
+    SourceMapError(String),
+    #[error("Declarations are not allowed directly inside of `:global` block")]
+    DeclarationsInGlobalBlock,
+    #[error("Style can declare only one struct with `@rcss(struct ..)`")]
+    DuplicateDeclare,
+    #[error("Style can extend only one other style with `@rcss(extend ..)`")]
+    DuplicateExtend,
+    #[error("Struct `{0}` declared with `@rcss(..)` can't have fields, they are generated from style classes")]
+    StructWithFields(String),
+    #[error(
+        "Unknown rule `@rcss({0})`, expected struct declaration, `extend path`, `mixin name`, `scope-vars` or `scope-ids`"
+    )]
+    UnknownRcssRule(String),
+    #[error("Mixin `{0}` is declared more than once")]
+    DuplicateMixin(String),
+    #[error(
+        "Mixin `{0}` can be declared only in style with declared struct (`@rcss(pub struct Name)`)"
+    )]
+    MixinWithoutStruct(String),
+    #[error("Mixin `{0}` can't use runtime interpolation, only constants are allowed")]
+    RuntimeVariableInMixin(String),
+    #[error("Invalid `@rcss(..)` rule: {0}")]
+    InvalidRcssRule(String),
+    #[error("{source}")]
+    WithLocation {
+        line: u32,
+        column: u32,
+        source: Box<Error>,
+    },
+}
+
+impl From<AtRuleError> for Error {
+    fn from(e: AtRuleError) -> Self {
+        match e {
+            AtRuleError::DuplicateDeclare => Error::DuplicateDeclare,
+            AtRuleError::DuplicateExtend => Error::DuplicateExtend,
+            AtRuleError::StructWithFields(name) => Error::StructWithFields(name),
+            AtRuleError::UnknownRule(rule) => Error::UnknownRcssRule(rule),
+            AtRuleError::DuplicateMixin(name) => Error::DuplicateMixin(name),
+            e => Error::InvalidRcssRule(e.to_string()),
+        }
+    }
+}
+
+impl Error {
+    /// Returns location of the error in processed css (if known).
+    /// Line is counted from 0, and column from 1 (in UTF-16 code units), same as in lightningcss.
+    pub fn location(&self) -> Option<ErrorLocation> {
+        match self {
+            Error::GenericParser(e) => e.loc.clone(),
+            Error::WithLocation { line, column, .. } => Some(ErrorLocation {
+                filename: String::new(),
+                line: *line,
+                column: *column,
+            }),
+            _ => None,
+        }
+    }
+    /// Replace location of the error (if any) with result of `f(line, column)`.
+    pub fn map_location(mut self, f: impl FnOnce(u32, u32) -> (u32, u32)) -> Self {
+        match &mut self {
+            Error::GenericParser(lightningcss::error::Error { loc: Some(loc), .. }) => {
+                (loc.line, loc.column) = f(loc.line, loc.column);
+            }
+            Error::WithLocation { line, column, .. } => (*line, *column) = f(*line, *column),
+            _ => {}
+        }
+        self
+    }
+    // Attach location of the rule to the error, if error has no location yet.
+    fn with_location(self, loc: Location) -> Self {
+        if self.location().is_some() {
+            return self;
+        }
+        Error::WithLocation {
+            line: loc.line,
+            column: loc.column,
+            source: Box::new(self),
+        }
+    }
 }
 
 impl SelectorVisitor {
@@ -98,96 +245,267 @@ impl SelectorVisitor {
 
         Ok(selector.into_owned())
     }
-    fn try_modify_parts(&mut self, selectors: &mut Selector<'_>) -> Result<(), Error> {
-        let class_name = self.append_class.clone();
-
-        // Iterate over selector components
-        // Split selector by its combinators
-        let mut combinators = selectors
-            .iter_raw_match_order()
-            .rev()
-            .filter_map(|x| x.as_combinator());
-        let chunks = selectors
-            .iter_raw_match_order()
-            .as_slice()
-            .split(|x| x.is_combinator())
-            .rev();
-
-        // Split selector by combinators
+    // Rename classes and scope each compound selector without class.
+    // Subject compound (last one) is not scoped with `scope_subject = false`,
+    // it is used for selectors of `:is(..)`, which subject is the element of outer selector.
+    // Returns true if subject compound contains class.
+    fn try_modify_parts(
+        &mut self,
+        selectors: &mut Selector<'_>,
+        scope_subject: bool,
+    ) -> Result<bool, Error> {
+        let scope = self.scope_component();
+
+        // Iterate over selector components, each compound is scoped when its combinator is reached.
         let mut processed_selector = vec![];
+        // Pseudo-elements are separated from their compound with special combinator,
+        // scope should be placed before them (`a._scope::before`).
+        let mut pseudo_element_start = None;
 
-        for chunk in chunks {
-            if chunk.is_empty() {
+        for part in components_in_parse_order(selectors) {
+            if let Component::Combinator(combinator) = part {
+                if is_pseudo_element_combinator(combinator) {
+                    pseudo_element_start.get_or_insert(processed_selector.len());
+                } else {
+                    self.scope_compound(
+                        &mut processed_selector,
+                        pseudo_element_start.take(),
+                        &scope,
+                    );
+                    self.state.handle_combinator();
+                }
+                processed_selector.push(Component::Combinator(combinator));
                 continue;
             }
-            for part in chunk.into_iter().cloned() {
-                // println!("component: {:?}, state:{state:?}", part, state = self.state);
-                let part = match part {
-                    Component::Class(mut class) => {
-                        self.state.handle_class();
-                        // Use css_module only outside of :global
-                        if !self.state.global_selector {
-                            self.modify_classes(&mut class)?;
-                        }
-                        // return back class to collection
-                        Component::Class(class)
+            // println!("component: {:?}, state:{state:?}", part, state = self.state);
+            let part = match part {
+                Component::Class(mut class) => {
+                    self.state.handle_class();
+                    // Use css_module only outside of :global
+                    if !self.state.global_selector {
+                        self.modify_classes(&mut class)?;
                     }
-                    Component::NonTSPseudoClass(pseudo_class) => match pseudo_class {
-                        // Lightningcss uses global type only with css_modules enabled
-                        PseudoClass::Global { mut selector } => {
-                            self.match_global(&mut processed_selector, &mut selector)?;
+                    // return back class to collection
+                    Component::Class(class)
+                }
+                // Scoped id is unique to component, same as class.
+                Component::ID(mut id) if self.scope_ids => {
+                    self.state.handle_class();
+                    if !self.state.global_selector {
+                        self.modify_ids(&mut id)?;
+                    }
+                    Component::ID(id)
+                }
+                // Nesting selector refers to parent rule, which is already scoped.
+                Component::Nesting => {
+                    self.state.handle_class();
+                    Component::Nesting
+                }
+                // `:scope` and `:host` refer to component root, which is element with scope class.
+                Component::Scope | Component::Host(None) => {
+                    self.state.handle_class();
+                    scope.clone()
+                }
+                // `:host(.active)` is a root in specific state.
+                Component::Host(Some(selector)) => {
+                    self.state.handle_class();
+                    processed_selector.push(scope.clone());
+                    self.match_host(&mut processed_selector, selector)?;
+                    continue;
+                }
+                Component::NonTSPseudoClass(pseudo_class) => match pseudo_class {
+                    // Lightningcss uses global type only with css_modules enabled
+                    PseudoClass::Global { mut selector } => {
+                        self.match_global(&mut processed_selector, &mut selector)?;
+                        continue;
+                    }
+                    PseudoClass::CustomFunction { name, arguments } => {
+                        if &*name == "deep" {
+                            let mut selector =
+                                SelectorVisitor::token_list_to_selector(arguments.clone())?;
+                            if self.native_scope {
+                                self.add_scope_limit(&processed_selector);
+                            }
+
+                            self.match_deep(&mut processed_selector, &mut selector)?;
+
                             continue;
                         }
-                        PseudoClass::CustomFunction { name, arguments } => {
-                            if &*name == "deep" {
-                                let mut selector =
-                                    SelectorVisitor::token_list_to_selector(arguments.clone())?;
+                        if &*name == "global" {
+                            let mut selector =
+                                SelectorVisitor::token_list_to_selector(arguments.clone())?;
 
-                                self.match_deep(&mut processed_selector, &mut selector)?;
+                            self.match_global(&mut processed_selector, &mut selector)?;
 
-                                continue;
-                            }
-                            if &*name == "global" {
-                                let mut selector =
-                                    SelectorVisitor::token_list_to_selector(arguments.clone())?;
+                            continue;
+                        }
+                        if &*name == "host-context" {
+                            let selector =
+                                SelectorVisitor::token_list_to_selector(arguments.clone())?;
 
-                                self.match_global(&mut processed_selector, &mut selector)?;
+                            self.match_host_context(
+                                &mut processed_selector,
+                                selector,
+                                scope.clone(),
+                            );
 
-                                continue;
-                            }
-                            Component::NonTSPseudoClass(PseudoClass::CustomFunction {
-                                name,
-                                arguments,
-                            })
+                            continue;
                         }
-                        pseudo_class => Component::NonTSPseudoClass(pseudo_class),
-                    },
-                    rest => rest,
-                };
-                processed_selector.push(part)
-            }
-            if !self.state.class_found {
-                Self::append_class(&self.state, &mut processed_selector, &class_name)?;
-            }
-            if let Some(combinator) = combinators.next() {
-                processed_selector.push(Component::Combinator(combinator));
-            }
-            self.state.handle_combinator();
+                        Component::NonTSPseudoClass(PseudoClass::CustomFunction { name, arguments })
+                    }
+                    pseudo_class => Component::NonTSPseudoClass(pseudo_class),
+                },
+                Component::Negation(selectors) => {
+                    Component::Negation(self.modify_nested(&selectors, false)?.0)
+                }
+                // Element matched by `:is(.a, .b)` always has one of classes.
+                Component::Is(selectors) => {
+                    let (selectors, class_found) = self.modify_nested(&selectors, false)?;
+                    if class_found {
+                        self.state.handle_class();
+                    }
+                    Component::Is(selectors)
+                }
+                Component::Where(selectors) => {
+                    let (selectors, class_found) = self.modify_nested(&selectors, false)?;
+                    if class_found {
+                        self.state.handle_class();
+                    }
+                    Component::Where(selectors)
+                }
+                Component::Any(prefix, selectors) => {
+                    let (selectors, class_found) = self.modify_nested(&selectors, false)?;
+                    if class_found {
+                        self.state.handle_class();
+                    }
+                    Component::Any(prefix, selectors)
+                }
+                Component::NthOf(data) => {
+                    let (selectors, class_found) = self.modify_nested(data.selectors(), false)?;
+                    if class_found {
+                        self.state.handle_class();
+                    }
+                    Component::NthOf(NthOfSelectorData::new(*data.nth_data(), selectors))
+                }
+                // View transition pseudo-elements originate from document root, so they are not scoped.
+                Component::PseudoElement(mut pseudo) => {
+                    let names = &self.scoped_names;
+                    if crate::global_names::visit_pseudo_element(
+                        &mut pseudo,
+                        &mut |kind, _, name| crate::global_names::scoped_name(names, kind, name),
+                    ) {
+                        self.state.handle_class();
+                    }
+                    Component::PseudoElement(pseudo)
+                }
+                // Selectors of `:has(..)` match other elements (descendants or siblings),
+                // so each of their compounds is scoped.
+                Component::Has(selectors) => {
+                    Component::Has(self.modify_nested(&selectors, true)?.0)
+                }
+                rest => rest,
+            };
+            processed_selector.push(part)
+        }
+        let subject_class_found = self.state.class_found;
+        if scope_subject {
+            self.scope_compound(&mut processed_selector, pseudo_element_start, &scope);
         }
+        self.state.handle_combinator();
         // println!("processed_selector: {:?}", processed_selector);
         *selectors = Selector::from(processed_selector);
-        Ok(())
+        Ok(subject_class_found)
     }
-    fn append_class(
-        state: &SelectorState,
-        selector_components: &mut Vec<Component>,
-        class_name: &String,
-    ) -> Result<(), Error> {
-        // append class only if not in :deep and :global
-        if !state.deep_selector && !state.global_selector {
-            selector_components.push(Component::Class(class_name.clone().into()));
+    // Process selectors of functional pseudo-class, like `:not(..)` or `:has(..)`.
+    // Returns processed selectors, and whether subject of each selector contains class.
+    fn modify_nested<'i>(
+        &mut self,
+        selectors: &[Selector<'i>],
+        scope_subject: bool,
+    ) -> Result<(Box<[Selector<'i>]>, bool), Error> {
+        let state = self.state.clone();
+        let mut all_found = true;
+        let mut result = Vec::with_capacity(selectors.len());
+        for selector in selectors {
+            let mut components = components_in_parse_order(selector);
+            // Relative selector (like `> .icon` in `:has(> .icon)`) starts with anchor,
+            // that refers to element of outer selector, so it is kept as is.
+            let anchor = match &components[..] {
+                [Component::Scope | Component::Nesting, Component::Combinator(_), ..] => {
+                    components.drain(..2).collect()
+                }
+                _ => vec![],
+            };
+            let mut selector = Selector::from(components);
+            self.state.class_found = false;
+            all_found &= self.try_modify_parts(&mut selector, scope_subject)?;
+            let components = anchor
+                .into_iter()
+                .chain(components_in_parse_order(&selector))
+                .collect::<Vec<_>>();
+            result.push(Selector::from(components));
+        }
+        self.state = state;
+        Ok((result.into_boxed_slice(), all_found))
+    }
+    // Scope compound, if it has no class.
+    // Scope is inserted before pseudo-elements of compound (at `pseudo_element_start`), or at its end.
+    fn scope_compound<'i>(
+        &self,
+        selector_components: &mut Vec<Component<'i>>,
+        pseudo_element_start: Option<usize>,
+        scope: &Component<'i>,
+    ) {
+        // append scope only if not in :deep and :global
+        if self.state.class_found
+            || self.state.deep_selector
+            || self.state.global_selector
+            || self.native_scope
+        {
+            return;
+        }
+        let position = pseudo_element_start.unwrap_or(selector_components.len());
+        selector_components.insert(position, scope.clone());
+    }
+    // Returns selector component that matches elements of component (scope class or scope attribute).
+    fn scope_component<'i>(&self) -> Component<'i> {
+        if self.native_scope {
+            return Component::Scope;
+        }
+        match &self.scope_attr {
+            Some(attr) => Component::AttributeInNoNamespaceExists {
+                local_name: attr.clone().into(),
+                local_name_lower: attr.to_lowercase().into(),
+            },
+            None => Component::Class(self.append_class.clone().into()),
+        }
+    }
+    // Content after `:deep(..)` belongs to other components, so other rules should not reach it.
+    // For `.slot :deep(.item)` limit is `.slot > *`, limits are collected only from top level rules,
+    // since nested selectors are relative to parent rule.
+    fn add_scope_limit(&mut self, prefix: &[Component<'_>]) {
+        self.deep_found = true;
+        if self.rule_depth != 1 {
+            return;
+        }
+        let is_compound_end = |c: &Component<'_>| matches!(c, Component::Combinator(c) if !is_pseudo_element_combinator(*c));
+        let Some(end) = prefix.iter().rposition(is_compound_end) else {
+            return;
+        };
+        use lightningcss::traits::IntoOwned;
+        let limit = prefix[..end]
+            .iter()
+            .cloned()
+            .chain([
+                Component::Combinator(Combinator::Child),
+                Component::ExplicitUniversalType,
+            ])
+            .map(IntoOwned::into_owned)
+            .collect::<Vec<_>>();
+        let limit = Selector::from(limit);
+        if !self.scope_limits.contains(&limit) {
+            self.scope_limits.push(limit);
         }
-        Ok(())
     }
     fn match_global<'i>(
         &mut self,
@@ -219,6 +537,52 @@ impl SelectorVisitor {
         self.state.class_found = true;
         Ok(())
     }
+    // Classes in `:host(..)` describe state of component root, so they are renamed as usual.
+    fn match_host<'i>(
+        &mut self,
+        selector_components: &mut Vec<Component<'i>>,
+        selector: Selector<'i>,
+    ) -> Result<(), Error> {
+        for part in selector.iter_raw_parse_order_from(0).cloned() {
+            let part = match part {
+                Component::Class(mut class) if !self.state.global_selector => {
+                    self.modify_classes(&mut class)?;
+                    Component::Class(class)
+                }
+                Component::ID(mut id) if self.scope_ids && !self.state.global_selector => {
+                    self.modify_ids(&mut id)?;
+                    Component::ID(id)
+                }
+                part => part,
+            };
+            selector_components.push(part);
+        }
+        Ok(())
+    }
+    // `:host-context(.dark)` matches component root inside of `.dark` ancestor, or root itself with `.dark`,
+    // so it is replaced with `:is(.dark .SCOPE, .dark.SCOPE)`.
+    // Ancestors are usually outside of component, so selector is kept global.
+    fn match_host_context<'i>(
+        &mut self,
+        selector_components: &mut Vec<Component<'i>>,
+        selector: Selector<'i>,
+        scope: Component<'i>,
+    ) {
+        let context = selector
+            .iter_raw_parse_order_from(0)
+            .cloned()
+            .collect::<Vec<_>>();
+        let ancestor = context
+            .iter()
+            .cloned()
+            .chain([Component::Combinator(Combinator::Descendant), scope.clone()])
+            .collect::<Vec<_>>();
+        let itself = context.into_iter().chain([scope]).collect::<Vec<_>>();
+        selector_components.push(Component::Is(
+            vec![Selector::from(ancestor), Selector::from(itself)].into_boxed_slice(),
+        ));
+        self.state.handle_class();
+    }
     fn modify_classes(&mut self, class: &mut Ident<'_>) -> Result<(), Error> {
         let class_string = class.to_css_string(PrinterOptions::default())?;
         let modified = (*self.class_modify)(class_string.clone());
@@ -226,38 +590,201 @@ impl SelectorVisitor {
         *class = modified.into();
         Ok(())
     }
-    fn save_rcss_rule(&mut self, rcss_rule: RcssAtRuleConfig) {
-        // TODO: Emit error on multiple rcss rules
+    fn modify_ids(&mut self, id: &mut Ident<'_>) -> Result<(), Error> {
+        let id_string = id.to_css_string(PrinterOptions::default())?;
+        let modified = (*self.class_modify)(id_string.clone());
+        self.collect_ids.insert(id_string, modified.clone());
+        *id = modified.into();
+        Ok(())
+    }
+    fn rename_keyframes(&self, name: &mut KeyframesName<'_>) {
+        let (KeyframesName::Ident(CustomIdent(name)) | KeyframesName::Custom(name)) = name;
+        if let Some(scoped) = self.keyframes.get(&**name) {
+            *name = scoped.clone().into();
+        }
+    }
+    fn rename_animation(&self, name: &mut AnimationName<'_>) {
+        let (AnimationName::Ident(CustomIdent(name)) | AnimationName::String(name)) = name else {
+            return;
+        };
+        if let Some(scoped) = self.keyframes.get(&**name) {
+            *name = scoped.clone().into();
+        }
+    }
+    // Remove `composes:` declarations from rule, and remember composed classes.
+    fn extract_composes(
+        &mut self,
+        style: &mut lightningcss::rules::style::StyleRule<'_, RcssAtRuleConfig<'_>>,
+    ) -> Result<(), Error> {
+        // Without css_modules, lightningcss keeps `composes` as unparsed property.
+        let is_composes = |p: &Property<'_>| matches!(p, Property::Unparsed(unparsed) if unparsed.property_id == PropertyId::Composes);
+        let declarations = &mut style.declarations;
+        if !declarations
+            .declarations
+            .iter()
+            .chain(&declarations.important_declarations)
+            .any(is_composes)
+        {
+            return Ok(());
+        }
+        // Same as in css modules, composes can be used only in rule with single class.
+        let class = match style.selectors.0.as_slice() {
+            [selector] => match selector.iter_raw_match_order().as_slice() {
+                [Component::Class(class)] => class.to_string(),
+                _ => return Err(Error::ComposesNotAllowed),
+            },
+            _ => return Err(Error::ComposesNotAllowed),
+        };
+        let mut composed = vec![];
+        for list in [
+            &mut declarations.declarations,
+            &mut declarations.important_declarations,
+        ] {
+            for property in list.iter().filter(|p| is_composes(p)) {
+                let value = property.value_to_css_string(PrinterOptions::default())?;
+                composed.extend(parse_composes(&value)?);
+            }
+            list.retain(|p| !is_composes(p));
+        }
+        self.composes.entry(class).or_default().extend(composed);
+        Ok(())
+    }
+    // Duplicated rules are rejected by `RcssAtRuleParser`.
+    fn save_rcss_rule(&mut self, rcss_rule: &RcssAtRuleConfig<'_>) -> Result<(), Error> {
         match rcss_rule {
-            RcssAtRuleConfig::Struct(item_struct) => self.declare = Some(item_struct),
-            RcssAtRuleConfig::Extend(path) => self.extend = Some(path),
+            RcssAtRuleConfig::Struct(item_struct) => self.declare = Some((**item_struct).clone()),
+            RcssAtRuleConfig::Extend { path, new_classes } => {
+                self.extend = Some(path.clone());
+                self.extend_new_classes = *new_classes;
+            }
+            // Handled before visiting, see `DeclarationsCollector`.
+            RcssAtRuleConfig::ScopeVars | RcssAtRuleConfig::ScopeIds => {}
+            // Unwrapped before visiting, see `take_global_rules`.
+            RcssAtRuleConfig::Global(_) => {}
+            // Mixin is not part of style, its declarations are inlined into other styles as is.
+            RcssAtRuleConfig::Mixin { name, declarations } => {
+                let declarations = declarations.to_css_string(PrinterOptions {
+                    minify: true,
+                    ..Default::default()
+                })?;
+                self.mixins.insert(name.to_string(), declarations);
+            }
         }
+        Ok(())
     }
 }
-impl<'i> lightningcss::visitor::Visitor<'i, crate::rcss_at_rule::RcssAtRuleConfig>
-    for SelectorVisitor
-{
+impl<'i> lightningcss::visitor::Visitor<'i, RcssAtRuleConfig<'i>> for SelectorVisitor {
     type Error = Error;
     fn visit_types(&self) -> VisitTypes {
-        visit_types!(SELECTORS | RULES)
+        visit_types!(SELECTORS | RULES | PROPERTIES | DASHED_IDENTS)
+    }
+
+    fn visit_dashed_ident(&mut self, ident: &mut DashedIdent<'_>) -> Result<(), Self::Error> {
+        if let Some(scoped) = self.custom_properties.get(&*ident.0) {
+            ident.0 = scoped.clone().into();
+        }
+        Ok(())
+    }
+
+    fn visit_property(&mut self, property: &mut Property<'i>) -> Result<(), Self::Error> {
+        match property {
+            Property::AnimationName(names, _) => {
+                for name in names.iter_mut() {
+                    self.rename_animation(name);
+                }
+            }
+            Property::Animation(animations, _) => {
+                for animation in animations.iter_mut() {
+                    self.rename_animation(&mut animation.name);
+                }
+            }
+            _ => {}
+        }
+        let names = &self.scoped_names;
+        crate::global_names::visit_property(property, &mut |kind, _, name| {
+            crate::global_names::scoped_name(names, kind, name)
+        });
+        use lightningcss::visitor::Visit;
+        property.visit_children(self)
     }
 
     fn visit_selector(&mut self, fragment: &mut Selector<'i>) -> Result<(), Self::Error> {
         // println!("fragment: {:?}", fragment);
         self.state.class_found = false;
-        self.try_modify_parts(fragment)?;
+        self.try_modify_parts(fragment, true)?;
 
         Ok(())
     }
+    fn visit_rule_list(
+        &mut self,
+        rules: &mut CssRuleList<'i, RcssAtRuleConfig<'i>>,
+    ) -> Result<(), Self::Error> {
+        let top_level = self.rule_depth == 0;
+        let mut result = Vec::with_capacity(rules.0.len());
+        for mut rule in rules.0.drain(..) {
+            let Some(mut global_rules) = take_global_rules(&mut rule, self.state.nested)? else {
+                self.visit_rule(&mut rule)?;
+                if top_level {
+                    let group = if std::mem::take(&mut self.deep_found) {
+                        ScopeGroup::Deep
+                    } else {
+                        ScopeGroup::Scoped
+                    };
+                    self.scope_groups.push(group);
+                }
+                result.push(rule);
+                continue;
+            };
+            // Rules of global block are inlined into parent, without scoping and renaming.
+            let global_selector = std::mem::replace(&mut self.state.global_selector, true);
+            let first_group = self.scope_groups.len();
+            self.visit_rule_list(&mut global_rules)?;
+            self.scope_groups[first_group..].fill(ScopeGroup::Unscoped);
+            self.state.global_selector = global_selector;
+            result.extend(global_rules.0);
+        }
+        rules.0 = result;
+        Ok(())
+    }
     fn visit_rule(
         &mut self,
-        rule: &mut CssRule<'i, crate::rcss_at_rule::RcssAtRuleConfig>,
+        rule: &mut CssRule<'i, RcssAtRuleConfig<'i>>,
     ) -> Result<(), Self::Error> {
+        self.rule_depth += 1;
+        let result = self.visit_rule_inner(rule);
+        self.rule_depth -= 1;
+        result
+    }
+}
+impl SelectorVisitor {
+    fn visit_rule_inner<'i>(
+        &mut self,
+        rule: &mut CssRule<'i, RcssAtRuleConfig<'i>>,
+    ) -> Result<(), Error> {
+        let names = &self.scoped_names;
+        crate::global_names::visit_rule(rule, &mut |kind, _, name| {
+            crate::global_names::scoped_name(names, kind, name)
+        });
         match rule {
             CssRule::Custom(rcss) => {
-                self.save_rcss_rule(rcss.clone());
+                self.save_rcss_rule(rcss)?;
                 *rule = CssRule::Ignored;
             }
+            CssRule::Keyframes(keyframes) => {
+                use lightningcss::visitor::Visit;
+                self.rename_keyframes(&mut keyframes.name);
+                keyframes.visit_children(self)?;
+            }
+            CssRule::Style(style) => {
+                use lightningcss::visitor::Visit;
+                let loc = style.loc;
+                self.extract_composes(style)
+                    .map_err(|e| e.with_location(loc))?;
+                let nested = std::mem::replace(&mut self.state.nested, true);
+                let result = style.visit_children(self);
+                self.state.nested = nested;
+                result.map_err(|e| e.with_location(loc))?;
+            }
             rule => {
                 use lightningcss::visitor::Visit;
                 rule.visit_children(self)?;
@@ -266,3 +793,239 @@ impl<'i> lightningcss::visitor::Visitor<'i, crate::rcss_at_rule::RcssAtRuleConfi
         Ok(())
     }
 }
+
+// Returns rules of `@global { ... }` or `:global { ... }` block, that should replace the block itself.
+fn take_global_rules<'i>(
+    rule: &mut CssRule<'i, RcssAtRuleConfig<'i>>,
+    nested: bool,
+) -> Result<Option<CssRuleList<'i, RcssAtRuleConfig<'i>>>, Error> {
+    match rule {
+        CssRule::Custom(RcssAtRuleConfig::Global(rules)) => {
+            Ok(Some(std::mem::replace(rules, CssRuleList(vec![]))))
+        }
+        CssRule::Style(style) if is_global_block(&style.selectors) => {
+            let declarations = &style.declarations;
+            if !declarations.declarations.is_empty()
+                || !declarations.important_declarations.is_empty()
+            {
+                return Err(Error::DeclarationsInGlobalBlock.with_location(style.loc));
+            }
+            let mut rules = std::mem::replace(&mut style.rules, CssRuleList(vec![]));
+            // On top level there is no parent rule, so implicit `&` of nested rules is removed.
+            if !nested {
+                for rule in &mut rules.0 {
+                    if let CssRule::Style(style) = rule {
+                        style.selectors.0.iter_mut().for_each(strip_leading_nesting);
+                    }
+                }
+            }
+            Ok(Some(rules))
+        }
+        _ => Ok(None),
+    }
+}
+
+// Check if selector list is `:global` (or `& :global` inside of other rule).
+fn is_global_block(selectors: &SelectorList<'_>) -> bool {
+    let [selector] = selectors.0.as_slice() else {
+        return false;
+    };
+    let is_global = |c: &Component<'_>| matches!(c, Component::NonTSPseudoClass(PseudoClass::Custom { name }) if &**name == "global");
+    match selector.iter_raw_parse_order_from(0).collect::<Vec<_>>()[..] {
+        [global] => is_global(global),
+        [Component::Nesting, Component::Combinator(Combinator::Descendant), global] => {
+            is_global(global)
+        }
+        _ => false,
+    }
+}
+
+// Returns components of selector in the order, that `Selector::from` expects.
+// Unlike `iter_raw_parse_order_from`, simple selectors of each compound keep their order.
+fn components_in_parse_order<'i>(selector: &Selector<'i>) -> Vec<Component<'i>> {
+    let mut combinators = selector
+        .iter_raw_match_order()
+        .rev()
+        .filter_map(|c| c.as_combinator());
+    let chunks = selector
+        .iter_raw_match_order()
+        .as_slice()
+        .split(|c| c.is_combinator())
+        .rev();
+    let mut components = vec![];
+    for (i, chunk) in chunks.enumerate() {
+        if i > 0 {
+            components.extend(combinators.next().map(Component::Combinator));
+        }
+        components.extend(chunk.iter().cloned());
+    }
+    components
+}
+
+// Combinators that separate pseudo-element (`::before`, `::part(..)`, `::slotted(..)`) from its compound.
+fn is_pseudo_element_combinator(combinator: Combinator) -> bool {
+    matches!(
+        combinator,
+        Combinator::PseudoElement | Combinator::Part | Combinator::SlotAssignment
+    )
+}
+
+fn strip_leading_nesting(selector: &mut Selector<'_>) {
+    let components = selector
+        .iter_raw_parse_order_from(0)
+        .cloned()
+        .collect::<Vec<_>>();
+    let rest = match &components[..] {
+        [Component::Nesting, Component::Combinator(Combinator::Descendant), rest @ ..] => rest,
+        [Component::Nesting, rest @ ..]
+            if !matches!(rest.first(), None | Some(Component::Combinator(_))) =>
+        {
+            rest
+        }
+        _ => return,
+    };
+    *selector = Selector::from(rest.to_vec());
+}
+
+/// Class, that is composed into other class with `composes:` property.
+#[derive(Clone, Debug)]
+pub enum ComposedClass {
+    /// Class from the same style (`composes: base`).
+    Local(String),
+    /// Class from other style struct (`composes: button from ::path::Struct`).
+    External { class: String, path: syn::Path },
+    /// Global class, that is used as is (`composes: button from global`).
+    Global(String),
+}
+
+// Parse value of `composes: class1 class2 [from (global | ::path::Struct)]`.
+fn parse_composes(value: &str) -> Result<Vec<ComposedClass>, Error> {
+    let (classes, from) = match value.split_once(" from ") {
+        Some((classes, from)) => (classes, Some(from.trim())),
+        None => (value, None),
+    };
+    let classes = classes.split_whitespace().map(String::from);
+    let composed = match from {
+        None => classes.map(ComposedClass::Local).collect::<Vec<_>>(),
+        Some("global") => classes.map(ComposedClass::Global).collect(),
+        Some(path) => {
+            let path = syn::parse_str::<syn::Path>(path)
+                .map_err(|e| Error::InvalidComposes(format!("{path}: {e}")))?;
+            classes
+                .map(|class| ComposedClass::External {
+                    class,
+                    path: path.clone(),
+                })
+                .collect()
+        }
+    };
+    if composed.is_empty() {
+        return Err(Error::InvalidComposes(value.to_owned()));
+    }
+    Ok(composed)
+}
+
+/// Collects names declared in style, that can be referenced before declaration,
+/// so they should be known before renaming:
+/// - names of `@keyframes` rules;
+/// - custom properties (if style uses `@rcss(scope-vars)`);
+/// - layers, containers, counters, grid areas and view transitions (see [`crate::global_names`]);
+/// - name of declared struct (used by readable naming strategy).
+///
+/// Also checks if style can be wrapped in native `@scope` (see [`crate::ScopingMode::Scope`]).
+#[derive(Default)]
+pub(crate) struct DeclarationsCollector {
+    pub keyframes: Vec<String>,
+    pub custom_properties: Vec<String>,
+    pub scope_vars: bool,
+    pub scope_ids: bool,
+    pub declared_struct: Option<String>,
+    // Layers, containers, counters, etc. (see `global_names` module).
+    pub scoped_names: Vec<(NameKind, String)>,
+    // Style uses inline `:global(..)`, or global block inside of at-rule,
+    // they can't be expressed with `@scope`, since all selectors in it are relative to scope root.
+    pub requires_rewriting: bool,
+    // State:
+    in_style_rule: bool,
+    in_at_rule: bool,
+}
+impl<'i> lightningcss::visitor::Visitor<'i, RcssAtRuleConfig<'i>> for DeclarationsCollector {
+    type Error = Error;
+    fn visit_types(&self) -> VisitTypes {
+        visit_types!(RULES | PROPERTIES | SELECTORS)
+    }
+    fn visit_rule(
+        &mut self,
+        rule: &mut CssRule<'i, RcssAtRuleConfig<'i>>,
+    ) -> Result<(), Self::Error> {
+        use lightningcss::visitor::Visit;
+        let is_global_rule = match rule {
+            CssRule::Custom(RcssAtRuleConfig::Global(_)) => true,
+            CssRule::Style(style) => is_global_block(&style.selectors),
+            _ => false,
+        };
+        if is_global_rule && self.in_at_rule && !self.in_style_rule {
+            self.requires_rewriting = true;
+        }
+        match rule {
+            CssRule::Keyframes(keyframes) => {
+                let (KeyframesName::Ident(CustomIdent(name)) | KeyframesName::Custom(name)) =
+                    &keyframes.name;
+                self.keyframes.push(name.to_string());
+            }
+            CssRule::Property(property) => {
+                self.custom_properties.push(property.name.0.to_string());
+            }
+            CssRule::Custom(RcssAtRuleConfig::ScopeVars) => self.scope_vars = true,
+            CssRule::Custom(RcssAtRuleConfig::ScopeIds) => self.scope_ids = true,
+            CssRule::Custom(RcssAtRuleConfig::Struct(item)) => {
+                self.declared_struct = Some(item.ident.to_string())
+            }
+            // Names declared in global block are not scoped.
+            CssRule::Custom(RcssAtRuleConfig::Global(_)) => return Ok(()),
+            CssRule::Style(style) if is_global_block(&style.selectors) => return Ok(()),
+            _ => {}
+        }
+        crate::global_names::visit_rule(rule, &mut |kind, declaration, name| {
+            if declaration {
+                self.scoped_names.push((kind, name.to_owned()));
+            }
+            None
+        });
+        let (in_style_rule, in_at_rule) = (self.in_style_rule, self.in_at_rule);
+        match rule {
+            CssRule::Style(_) => self.in_style_rule = true,
+            _ => self.in_at_rule = true,
+        }
+        let result = rule.visit_children(self);
+        (self.in_style_rule, self.in_at_rule) = (in_style_rule, in_at_rule);
+        result
+    }
+    fn visit_selector(&mut self, selector: &mut Selector<'i>) -> Result<(), Self::Error> {
+        let is_inline_global = |c: &Component<'_>| match c {
+            Component::NonTSPseudoClass(PseudoClass::Global { .. }) => true,
+            Component::NonTSPseudoClass(PseudoClass::CustomFunction { name, .. }) => {
+                &**name == "global"
+            }
+            _ => false,
+        };
+        if selector.iter_raw_match_order().any(is_inline_global) {
+            self.requires_rewriting = true;
+        }
+        Ok(())
+    }
+    fn visit_property(&mut self, property: &mut Property<'i>) -> Result<(), Self::Error> {
+        crate::global_names::visit_property(property, &mut |kind, declaration, name| {
+            if declaration {
+                self.scoped_names.push((kind, name.to_owned()));
+            }
+            None
+        });
+        if let Property::Custom(custom) = property {
+            if let CustomPropertyName::Custom(DashedIdent(name)) = &custom.name {
+                self.custom_properties.push(name.to_string());
+            }
+        }
+        Ok(())
+    }
+}
@@ -152,6 +152,16 @@ impl StyleTree {
         always_output_layer: bool,
         root_scope_id: impl Into<ScopeId>,
     ) -> Option<String> {
+        self.render_with_offsets(always_output_layer, root_scope_id)
+            .map(|(style, _)| style)
+    }
+
+    /// Same as `render`, but also returns byte offset of each layer style in rendered css.
+    pub fn render_with_offsets(
+        &self,
+        always_output_layer: bool,
+        root_scope_id: impl Into<ScopeId>,
+    ) -> Option<(String, Vec<(ScopeId, usize)>)> {
         let root_scope_id = root_scope_id.into();
         let root_scope = self.uniq_layers.get(&root_scope_id)?.clone();
 
@@ -165,7 +175,7 @@ impl StyleTree {
 
         if !always_output_layer && rest.peek().is_none() {
             // if no other layers just return plain css without layers.
-            return Some(root_scope.1.to_string());
+            return Some((root_scope.1.to_string(), vec![(root_scope_id, 0)]));
         }
         let mut ordered_layers: Vec<_> = rest
             .map(|(layer, (order, style))| (order, layer, style))
@@ -186,16 +196,18 @@ impl StyleTree {
         header.push(';');
 
         let mut style = header;
+        let mut offsets = Vec::with_capacity(ordered_layers.len());
 
         // Push all layer declaration to header
         for (_, scope_id, layer_impl) in ordered_layers {
             style.push_str("@layer ");
             style.push_str(scope_id);
-            style.push_str("{");
+            style.push('{');
+            offsets.push((scope_id.clone(), style.len()));
             style.push_str(layer_impl);
-            style.push_str("}");
+            style.push('}');
         }
-        Some(style)
+        Some((style, offsets))
     }
 }
 
@@ -32,12 +32,38 @@ fn collect_crates_path(build_dir: &Path) -> HashSet<PathBuf> {
 
 struct Config {
     disable_styles: bool,
+    targets: Option<String>,
 }
 impl Config {
     fn set_cfg(&self) {
         if self.disable_styles {
             println!("cargo:rustc-cfg=disable_styles");
         }
+        if let Some(targets) = &self.targets {
+            println!("cargo:rustc-env=RCSS_TARGETS={targets}");
+        }
+        // Proc macro can't read profile of build, so it is passed from build script.
+        // Cargo sets `release` for profiles that inherit from release, and `debug` for others.
+        let profile = std::env::var("PROFILE").unwrap_or_else(|_| "debug".to_owned());
+        println!("cargo:rustc-env=RCSS_PROFILE={profile}");
+    }
+}
+
+/// Targets can be set either as single browserslist query, or as array of queries.
+fn targets_from_toml(targets: &toml::Value) -> Option<String> {
+    match targets {
+        toml::Value::String(query) => Some(query.clone()),
+        toml::Value::Array(queries) => Some(
+            queries
+                .iter()
+                .filter_map(|q| q.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        _ => {
+            println!("cargo:warning=Expected string or array of strings in metadata.rcss.targets");
+            None
+        }
     }
 }
 
@@ -121,6 +147,7 @@ fn main() {
 
     let mut config = Config {
         disable_styles: false,
+        targets: None,
     };
     for c in crates {
         println!("cargo:rerun-if-changed={}", c.display());
@@ -139,6 +166,17 @@ fn main() {
                     config.disable_styles = true;
                 }
             }
+            if let Some(targets) = manifest.get("targets").and_then(targets_from_toml) {
+                match &config.targets {
+                    Some(existing) if existing != &targets => {
+                        println!(
+                            "cargo:warning=Ignoring targets \"{targets}\" from {}, using \"{existing}\"",
+                            c.display()
+                        );
+                    }
+                    _ => config.targets = Some(targets),
+                }
+            }
         }
     }
     config.set_cfg();
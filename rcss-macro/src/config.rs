@@ -0,0 +1,116 @@
+//! Configuration of crate that calls macro, read from its `[package.metadata.rcss]`.
+//!
+//! Unlike options from build.rs (which are set by root crate that uses `rcss-bundler`),
+//! these options are set per crate.
+
+use std::{collections::BTreeMap, path::PathBuf, sync::Mutex};
+
+use rcss_core::{Naming, NamingConfig, ScopingMode};
+
+/// Returns naming of style that is declared at current macro call site.
+pub fn naming() -> Result<Naming, String> {
+    let config = crate_config()?;
+    let crate_name = std::env::var("CARGO_CRATE_NAME").unwrap_or_default();
+    let file = proc_macro::Span::call_site().local_file();
+    Ok(config.naming.naming(&crate_name, file.as_deref()))
+}
+
+/// Returns how styles of crate are scoped (`scoping = "class" | "attribute" | "global"`).
+pub fn scoping() -> Result<ScopingMode, String> {
+    crate_config().map(|c| c.scoping)
+}
+
+/// Returns true if fields of classes should be checked by rustc `dead_code` lint
+/// (`warn-unused-classes = true`).
+pub fn warn_unused_classes() -> bool {
+    crate_config()
+        .map(|c| c.warn_unused_classes)
+        .unwrap_or_default()
+}
+
+/// Item that includes manifest of crate, so crate is rebuilt when its config changes
+/// (`proc_macro::tracked_path` is unstable).
+pub fn manifest_dependency() -> proc_macro2::TokenStream {
+    let Some(path) = manifest_path().filter(|path| path.is_file()) else {
+        return quote::quote! {};
+    };
+    let path = path.display().to_string();
+    quote::quote! {
+        const _: &[u8] = include_bytes!(#path);
+    }
+}
+
+fn manifest_path() -> Option<PathBuf> {
+    let dir = std::env::var("CARGO_MANIFEST_DIR").ok()?;
+    Some(PathBuf::from(dir).join("Cargo.toml"))
+}
+
+#[derive(Clone, Default)]
+struct CrateConfig {
+    naming: NamingConfig,
+    scoping: ScopingMode,
+    warn_unused_classes: bool,
+}
+
+// Manifest content and config parsed from it.
+type CachedConfig = (String, Result<CrateConfig, String>);
+
+// Macro can be loaded once for multiple crates (like in rust-analyzer),
+// so config is cached by manifest and parsed again only if manifest content is changed.
+fn crate_config() -> Result<CrateConfig, String> {
+    static CONFIGS: Mutex<BTreeMap<PathBuf, CachedConfig>> = Mutex::new(BTreeMap::new());
+    let Some(path) = manifest_path() else {
+        return Ok(CrateConfig::default());
+    };
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Ok(CrateConfig::default());
+    };
+    let mut configs = CONFIGS.lock().unwrap_or_else(|e| e.into_inner());
+    match configs.get(&path) {
+        Some((cached, config)) if *cached == content => config.clone(),
+        _ => {
+            let config = parse_config(&content);
+            configs.insert(path, (content, config.clone()));
+            config
+        }
+    }
+}
+
+fn parse_config(content: &str) -> Result<CrateConfig, String> {
+    let Ok(manifest) = content.parse::<toml::Value>() else {
+        return Ok(CrateConfig::default());
+    };
+    let Some(rcss) = manifest
+        .get("package")
+        .and_then(|p| p.get("metadata"))
+        .and_then(|m| m.get("rcss"))
+    else {
+        return Ok(CrateConfig::default());
+    };
+    // Should be in sync with `rcss_bundler::naming_from_metadata`.
+    // Profile of build is passed by build script, since macro itself can be built with other settings.
+    let profile = env!("RCSS_PROFILE");
+    let strategy = match rcss.get("naming") {
+        Some(toml::Value::Table(profiles)) => profiles.get(profile).and_then(|v| v.as_str()),
+        Some(value) => value.as_str(),
+        None => None,
+    };
+    let strategy = strategy
+        .map(|s| s.parse().map_err(|e: rcss_core::Error| e.to_string()))
+        .unwrap_or(Ok(Default::default()))?;
+    // Should be in sync with `rcss_bundler::scoping_from_metadata`.
+    let scoping = rcss
+        .get("scoping")
+        .and_then(|v| v.as_str())
+        .map(|s| s.parse().map_err(|e: rcss_core::Error| e.to_string()))
+        .unwrap_or(Ok(Default::default()))?;
+    let flag = |name: &str| rcss.get(name).and_then(|v| v.as_bool()).unwrap_or_default();
+    Ok(CrateConfig {
+        naming: NamingConfig {
+            strategy,
+            salt_scope_id: flag("salt-scope-id"),
+        },
+        scoping,
+        warn_unused_classes: flag("warn-unused-classes"),
+    })
+}
@@ -5,8 +5,10 @@
 //! Outputs CssOutput with all classes and empty css strings and uniq class name.
 //!
 //! Can generate false positives when property or at-rule arguments receive some dot prefixed identifiers.
-use proc_macro2::{TokenStream, TokenTree};
-use rcss_core::{ClassInfo, CssOutput};
+use std::collections::BTreeMap;
+
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use rcss_core::{ClassInfo, CssOutput, RuntimeVariable};
 
 pub fn parse(input: TokenStream) -> CssOutput {
     let Ok(v) = std::env::var("RUST_ANALYZER_INTERNALS_DO_NOT_USE") else {
@@ -18,13 +20,40 @@ pub fn parse(input: TokenStream) -> CssOutput {
     parse_inner(input)
 }
 
+/// Returns spans of classes in macro input, used to point diagnostics to the class in style.
+pub fn class_spans(input: TokenStream) -> BTreeMap<String, Span> {
+    parse_inner(input)
+        .classes_map()
+        .iter()
+        .filter_map(|(class, info)| Some((class.clone(), info.original_span?)))
+        .collect()
+}
+
 fn parse_inner(input: TokenStream) -> CssOutput {
+    let runtime_variables = crate::helpers::interpolated_expressions(input.clone())
+        .into_iter()
+        .filter(|tokens| !rcss_core::interpolate::is_const_path(&tokens.to_string()))
+        .enumerate()
+        .map(|(id, tokens)| RuntimeVariable {
+            name: format!("--v{id}"),
+            expr: tokens.to_string(),
+            original_tokens: Some(tokens),
+        })
+        .collect();
     let mut stack = vec![input];
     let mut classes = Vec::new();
+    let mut ids = Vec::new();
+    let mut keyframes = Vec::new();
+    let mut layers = Vec::new();
+    let mut custom_properties = Vec::new();
+    let mut scope_vars = false;
+    let mut scope_ids = false;
     let mut declare = None;
     let mut extends = None;
+    let mut extend_new_classes = false;
+    let mut mixins = Vec::new();
     while let Some(input) = stack.pop() {
-        let mut tokens = input.into_iter().peekable();
+        let mut tokens = skip_global_blocks(input).into_iter().peekable();
         while let Some(token) = tokens.next() {
             let punct = match token {
                 TokenTree::Punct(punct) => punct,
@@ -36,29 +65,84 @@ fn parse_inner(input: TokenStream) -> CssOutput {
                     continue;
                 }
             };
-            match punct.as_char() {
+            let at_rule = match punct.as_char() {
                 '.' => {
                     // process later
+                    None
+                }
+                // id is collected same as class name (hex colors are filtered later)
+                '#' => Some("id".to_owned()),
+                '$' => {
+                    // skip interpolated expression, it is not part of css
+                    if let Some(TokenTree::Group(_)) = tokens.peek() {
+                        tokens.next();
+                    }
+                    continue;
                 }
                 '@' => {
                     // at rule
-                    let Some(rcss_rule) = crate::helpers::parse_rcss_config(&mut tokens) else {
+                    if let Some(rcss_rule) = crate::helpers::parse_rcss_config(&mut tokens) {
+                        match rcss_rule {
+                            rcss_core::rcss_at_rule::RcssAtRuleConfig::Struct(item_struct) => {
+                                declare = Some(*item_struct)
+                            }
+                            rcss_core::rcss_at_rule::RcssAtRuleConfig::Extend {
+                                path,
+                                new_classes,
+                            } => {
+                                extends = Some(path);
+                                extend_new_classes = new_classes;
+                            }
+                            rcss_core::rcss_at_rule::RcssAtRuleConfig::ScopeVars => {
+                                scope_vars = true
+                            }
+                            rcss_core::rcss_at_rule::RcssAtRuleConfig::ScopeIds => scope_ids = true,
+                            // `@global` is not parsed as `@rcss(..)` rule.
+                            rcss_core::rcss_at_rule::RcssAtRuleConfig::Global(_) => {}
+                            // Declarations of mixin are not needed for IDE, only its name.
+                            rcss_core::rcss_at_rule::RcssAtRuleConfig::Mixin { name, .. } => {
+                                mixins.push(name.to_string())
+                            }
+                        }
+                        continue;
+                    }
+                    // keyframes and layer names are collected same as class name
+                    let Some(TokenTree::Ident(rule)) = tokens.peek() else {
                         continue;
                     };
-                    match rcss_rule {
-                        rcss_core::rcss_at_rule::RcssAtRuleConfig::Struct(item_struct) => {
-                            declare = Some(item_struct)
-                        }
-                        rcss_core::rcss_at_rule::RcssAtRuleConfig::Extend(path) => {
-                            extends = Some(path)
+                    let rule = rule.to_string();
+                    if !matches!(&*rule, "keyframes" | "layer") {
+                        continue;
+                    }
+                    tokens.next();
+                    Some(rule)
+                }
+                '-' => {
+                    // custom property declaration `--name: value`
+                    if !matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '-') {
+                        continue;
+                    }
+                    let mut ident = vec![TokenTree::Punct(punct)];
+                    ident.extend(tokens.next());
+                    while let Some(tt) = tokens.peek() {
+                        match tt {
+                            TokenTree::Ident(_) | TokenTree::Literal(_) => {}
+                            TokenTree::Punct(p) if p.as_char() == '-' => {}
+                            _ => break,
                         }
+                        ident.extend(tokens.next());
+                    }
+                    if ident.len() > 2
+                        && matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == ':')
+                    {
+                        custom_properties.push(ident);
                     }
                     continue;
                 }
                 _ => {
                     continue;
                 }
-            }
+            };
             // ignore literals after dot (prevent parsing something like .32);
             if let Some(TokenTree::Literal(_)) = tokens.peek() {
                 continue;
@@ -110,38 +194,91 @@ fn parse_inner(input: TokenStream) -> CssOutput {
                     }
                 }
             }
-            if !ident.is_empty() {
-                classes.push(ident);
+            if ident.is_empty() {
+                continue;
+            }
+            match at_rule.as_deref() {
+                Some("keyframes") => keyframes.push(ident),
+                Some("id") => ids.push(ident),
+                Some(_) => layers.push(ident),
+                None => classes.push(ident),
             }
         }
     }
-    let output = CssOutput::create_from_fields(
+    let mut output = CssOutput::create_from_fields(
         String::from(""),
         String::from(""),
         declare,
         extends,
-        classes
-            .into_iter()
-            .map(|ident| {
-                let first_span = ident.first().unwrap().span();
-                let mut span = first_span;
-                let ident = ident.into_iter();
-                let mut ident_str = String::new();
-                for tt in ident {
-                    ident_str.push_str(&tt.to_string());
-                    span = span.join(tt.span()).unwrap_or(span);
-                }
-                (
-                    ident_str.clone(),
-                    ClassInfo {
-                        class_name: ident_str,
-                        original_span: Some(span),
-                    },
-                )
-            })
-            .collect(),
+        classes.into_iter().map(ident_to_class_info).collect(),
+        runtime_variables,
     );
+    output.set_extend_new_classes(extend_new_classes);
+    output
+        .mixins_mut()
+        .extend(mixins.into_iter().map(|name| (name, String::new())));
+    output
+        .keyframes_map_mut()
+        .extend(keyframes.into_iter().map(ident_to_class_info));
     output
+        .scoped_names_map_mut(rcss_core::NameKind::Layer)
+        .extend(layers.into_iter().map(ident_to_class_info));
+    if scope_ids {
+        output.ids_map_mut().extend(
+            ids.into_iter()
+                .map(ident_to_class_info)
+                .filter(|(id, _)| !is_hex_color(id)),
+        );
+    }
+    if scope_vars {
+        output
+            .custom_properties_map_mut()
+            .extend(custom_properties.into_iter().map(ident_to_class_info));
+    }
+    output
+}
+
+// Remove content of `@global { .. }` and `:global { .. }` blocks, classes inside them are not renamed.
+fn skip_global_blocks(input: TokenStream) -> TokenStream {
+    let mut result: Vec<TokenTree> = Vec::new();
+    for token in input {
+        if let TokenTree::Group(group) = &token {
+            let is_global = group.delimiter() == Delimiter::Brace
+                && matches!(
+                    &result[..],
+                    [.., TokenTree::Punct(p), TokenTree::Ident(i)]
+                        if matches!(p.as_char(), '@' | ':') && i == "global"
+                );
+            if is_global {
+                continue;
+            }
+        }
+        result.push(token);
+    }
+    result.into_iter().collect()
+}
+
+// Values like `#fff` are tokenized in the same way as ids.
+fn is_hex_color(value: &str) -> bool {
+    matches!(value.len(), 3 | 4 | 6 | 8) && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn ident_to_class_info(ident: Vec<TokenTree>) -> (String, ClassInfo) {
+    let first_span = ident.first().unwrap().span();
+    let mut span = first_span;
+    let ident = ident.into_iter();
+    let mut ident_str = String::new();
+    for tt in ident {
+        ident_str.push_str(&tt.to_string());
+        span = span.join(tt.span()).unwrap_or(span);
+    }
+    (
+        ident_str.clone(),
+        ClassInfo {
+            class_name: ident_str,
+            original_span: Some(span),
+        },
+    )
 }
 
 #[cfg(test)]
@@ -224,6 +361,130 @@ mod test {
         assert_eq!(elements_list, expected_list);
     }
 
+    #[test]
+    fn skip_interpolated_expressions() {
+        let input = r#"
+        .my-class {
+            color: ${theme.color};
+            width: ${"10px"};
+            padding: ${ if wide { foo.bar } else { 0 } };
+        }
+        "#;
+        let output = super::parse_inner(input.parse().unwrap());
+        let elements_list = output.classes_list().collect::<Vec<_>>();
+        assert_eq!(elements_list, vec!["my-class"]);
+        let exprs = output
+            .runtime_variables()
+            .iter()
+            .map(|v| v.expr.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            exprs,
+            vec!["theme . color", "if wide { foo . bar } else { 0 }"]
+        );
+    }
+
+    #[test]
+    fn keyframes_parse() {
+        let input = r#"
+        .spinner {
+            animation: spin 1s;
+        }
+        @keyframes spin {
+            from { transform: rotate(0deg); }
+        }
+        @keyframes fade-in {
+            from { opacity: 0; }
+        }
+        @layer base.reset {
+            .button { color: red; }
+        }
+        "#;
+        let output = super::parse_inner(input.parse().unwrap());
+        let elements_list = output.classes_list().collect::<Vec<_>>();
+        assert_eq!(elements_list, vec!["button", "spinner"]);
+        let keyframes = output.keyframes_map().keys().collect::<Vec<_>>();
+        assert_eq!(keyframes, vec!["fade-in", "spin"]);
+        let layers = output
+            .scoped_names_map(rcss_core::NameKind::Layer)
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>();
+        assert_eq!(layers, vec!["base"]);
+    }
+
+    #[test]
+    fn scope_vars_parse() {
+        let input = r#"
+        @rcss(scope-vars);
+        .card {
+            --accent-color: red;
+            margin: -1px;
+            color: var(--accent-color);
+        }
+        "#;
+        let output = super::parse_inner(input.parse().unwrap());
+        let elements_list = output.classes_list().collect::<Vec<_>>();
+        assert_eq!(elements_list, vec!["card"]);
+        let vars = output.custom_properties_map().keys().collect::<Vec<_>>();
+        assert_eq!(vars, vec!["--accent-color"]);
+    }
+
+    #[test]
+    fn scope_ids_parse() {
+        let input = r#"
+        @rcss(scope-ids);
+        #dialog {
+            color: #fff;
+            background: #abcdef;
+        }
+        #close-button {
+            color: red;
+        }
+        .icon {
+            color: blue;
+        }
+        "#;
+        let output = super::parse_inner(input.parse().unwrap());
+        let elements_list = output.classes_list().collect::<Vec<_>>();
+        assert_eq!(elements_list, vec!["icon"]);
+        let ids = output.ids_map().keys().collect::<Vec<_>>();
+        assert_eq!(ids, vec!["close-button", "dialog"]);
+    }
+
+    #[test]
+    fn skip_global_blocks() {
+        let input = r#"
+        .card {
+            color: red;
+            :global {
+                .icon { color: blue; }
+            }
+        }
+        :global {
+            .reset { margin: 0; }
+        }
+        @global {
+            .body { margin: 0; }
+        }
+        .title:global(.dark) {
+            color: white;
+        }
+        "#;
+        let output = super::parse_inner(input.parse().unwrap());
+        let elements_list = output.classes_list().collect::<Vec<_>>();
+        // Classes in `:global(..)` still produce false positives.
+        assert_eq!(elements_list, vec!["card", "dark", "title"]);
+    }
+
+    #[test]
+    fn class_spans() {
+        let input = ".used { color: red; }\n.not-used { color: blue; }";
+        let spans = super::class_spans(input.parse().unwrap());
+        assert_eq!(spans.keys().collect::<Vec<_>>(), vec!["not-used", "used"]);
+        assert_eq!(spans["not-used"].start().line, 2);
+    }
+
     #[test]
     fn mod_declare_and_extend_parse() {
         let input = r#"
@@ -1,12 +1,14 @@
 use std::{collections::BTreeMap, iter::Peekable};
 
-use proc_macro2::{Literal, TokenTree};
+use proc_macro2::{Delimiter, LineColumn, Literal, Span, TokenStream, TokenTree};
+use quote::ToTokens;
 
-use rcss_core::rcss_at_rule::RcssAtRuleConfig;
+use rcss_core::{rcss_at_rule::RcssAtRuleConfig, ClassInfo, NameKind};
 
 // TODO: add ident checks and other "token_trees"
 /// Get macro input from macro call source text.
-/// Input should be in format: `css! { ... }`
+/// Input should be in format: `css! { ... }`, or `css!(r#"..."#)` with single string literal,
+/// in which case value of literal is returned.
 /// And can be retrived in function like proc-macro `Span::call_site().source_text()`.
 ///
 /// Example:
@@ -22,19 +24,105 @@ use rcss_core::rcss_at_rule::RcssAtRuleConfig;
 /// assert_eq!(macro_input, ".my-class {\n      color: red;\n  }");
 /// ```
 pub fn macro_input(source_text: &str) -> Option<String> {
-    // 1. Find macro call group (any type of braces)
-    // 2. skip whitespaces
-    // 3. return rest of the string or None, if group wasn't found
-    let (_path, group_start) = source_text.split_once(|c| "{[(".contains(c))?;
-    let (group, _end) = group_start.rsplit_once(|c| "}])".contains(c))?;
+    // Should be in sync with `macro_visit::literal_input`, used by bundler.
+    if let Some(literal) = macro_literal(source_text) {
+        return syn::parse_str::<syn::LitStr>(literal)
+            .ok()
+            .map(|literal| literal.value());
+    }
+    macro_group(source_text).map(ToOwned::to_owned)
+}
+
+/// Returns source text of string literal (like `r#"..."#`), if it is the only macro input.
+pub fn macro_literal(source_text: &str) -> Option<&str> {
+    macro_group(source_text).filter(|group| syn::parse_str::<syn::LitStr>(group).is_ok())
+}
 
-    let trimed = group.trim();
+// 1. Find macro call group (any type of braces)
+// 2. skip whitespaces
+// 3. return rest of the string or None, if group wasn't found
+fn macro_group(source_text: &str) -> Option<&str> {
+    let start = macro_input_start(source_text)?;
+    let (group, _end) = source_text[start..].rsplit_once(|c| "}])".contains(c))?;
+    Some(group.trim_end())
+}
 
-    Some(trimed.to_owned())
+/// Returns byte offset of the first non whitespace character inside macro call group.
+/// Position of css errors are relative to this offset.
+fn macro_input_start(source_text: &str) -> Option<usize> {
+    let group_start = source_text.find(|c| "{[(".contains(c))? + 1;
+    let group = &source_text[group_start..];
+    let whitespaces = group.len() - group.trim_start().len();
+    Some(group_start + whitespaces)
+}
+
+/// Find span of the token that contains position of css error.
+///
+/// `line` (counted from 0) and `column` (counted from 1 in UTF-16 code units) are
+/// relative to the output of `macro_input` (or to source of literal, returned by `macro_literal`),
+/// as reported by lightningcss.
+/// `tokens` should be the macro input, that was used to produce `source_text`,
+/// and `start` is position of `source_text` in rust file (`Span::call_site().start()`).
+///
+/// Returns None if position can't be mapped to any token.
+pub fn span_at_location(
+    source_text: &str,
+    tokens: TokenStream,
+    start: LineColumn,
+    line: u32,
+    column: u32,
+) -> Option<Span> {
+    // Line is counted from 1, so zero means that span locations are not available.
+    if start.line == 0 {
+        return None;
+    }
+    let input_start = macro_input_start(source_text)?;
+    let input = &source_text[input_start..];
+    let error_offset =
+        input_start + rcss_core::source_map::offset_at(input, line, column.saturating_sub(1));
+
+    // Position of error in rust file, columns of spans are counted in chars.
+    let before = &source_text[..error_offset];
+    let error_position = match before.rfind('\n') {
+        Some(line_start) => LineColumn {
+            line: start.line + before.matches('\n').count(),
+            column: before[line_start + 1..].chars().count(),
+        },
+        None => LineColumn {
+            line: start.line,
+            column: start.column + before.chars().count(),
+        },
+    };
+
+    let mut last_span = None;
+    let mut stack = vec![tokens.into_iter()];
+    while let Some(iter) = stack.last_mut() {
+        let Some(token) = iter.next() else {
+            stack.pop();
+            continue;
+        };
+        let span = match token {
+            TokenTree::Group(group) => {
+                stack.push(group.stream().into_iter());
+                continue;
+            }
+            token => token.span(),
+        };
+        // First token that ends after error position is the one that contains it
+        // (or the one that follows, if error is pointing to whitespace).
+        if span.end() > error_position {
+            return Some(span);
+        }
+        last_span = Some(span);
+    }
+    last_span
 }
 
 #[cfg(test)]
 mod test {
+    // Position of the first character of input, parsed from string.
+    const START: proc_macro2::LineColumn = proc_macro2::LineColumn { line: 1, column: 0 };
+
     #[test]
     fn check_macro_input_extractor() {
         let input = r#"
@@ -50,11 +138,121 @@ mod test {
             ".my-class {\n                color: red;\n            }"
         );
     }
+
+    #[test]
+    fn check_macro_input_literal() {
+        let input = r##"
+        css!(r#"
+            .my-class { content: '{'; width: 3em; color: #0ed; }
+        "#)
+        "##;
+        let css = super::macro_input(input).unwrap();
+        assert_eq!(
+            css,
+            "\n            .my-class { content: '{'; width: 3em; color: #0ed; }\n        "
+        );
+        let input = r#"css! { ".a { color: \"red\" }" }"#;
+        let css = super::macro_input(input).unwrap();
+        assert_eq!(css, r#".a { color: "red" }"#);
+    }
+
+    #[test]
+    fn check_error_span_location() {
+        let input = "css! {\n    .my-class { color: red; }\n    .broken..class { color: red; }\n}";
+        let css = super::macro_input(input).unwrap();
+        let error = rcss_core::CssProcessor::process_style(&css).unwrap_err();
+        let location = error.location().unwrap();
+
+        let tokens: proc_macro2::TokenStream = input.parse().unwrap();
+        let Some(proc_macro2::TokenTree::Group(group)) = tokens.into_iter().nth(2) else {
+            unreachable!()
+        };
+        let span =
+            super::span_at_location(input, group.stream(), START, location.line, location.column)
+                .unwrap();
+        assert_eq!(span.source_text().unwrap(), ".");
+        assert_eq!(span.start().line, 3);
+        assert_eq!(span.start().column, 12);
+    }
+
+    #[test]
+    fn check_nested_field_clash() {
+        use super::CssOutputGenerateExt;
+        let style =
+            ".keyframes { animation: keyframes 1s; } @keyframes keyframes { to { opacity: 0; } }";
+        let output = rcss_core::CssProcessor::process_style(style).unwrap();
+        let generated = output.generate(false).to_string();
+        assert!(generated.contains("compile_error"));
+        assert!(generated.contains("Class `keyframes` clashes with field `keyframes`"));
+
+        // Class with the same name is allowed, if style has no keyframes.
+        let output = rcss_core::CssProcessor::process_style(".keyframes { color: red; }").unwrap();
+        assert!(!output.generate(false).to_string().contains("compile_error"));
+    }
+
+    #[test]
+    fn check_lint_mode_keeps_pub_fields() {
+        use super::CssOutputGenerateExt;
+        let output =
+            rcss_core::CssProcessor::process_style("@rcss(pub struct A); .a { color: red; }")
+                .unwrap();
+        let generated = output.generate(true).to_string();
+        assert!(generated.contains("pub a : & 'static str"));
+        assert!(!generated.contains("pub (crate) a"));
+
+        let output =
+            rcss_core::CssProcessor::process_style("@rcss(struct A); .a { color: red; }").unwrap();
+        let generated = output.generate(true).to_string();
+        assert!(generated.contains("pub (crate) a : & 'static str"));
+    }
+
+    #[test]
+    fn check_error_span_in_escaped_literal() {
+        let input = r#"css!(".a { content: \"x\"; } .x..x { }")"#;
+        let css = super::macro_input(input).unwrap();
+        let literal = super::macro_literal(input).unwrap();
+        let options = rcss_core::CssProcessorOptions::new().literal(literal);
+        let error =
+            rcss_core::CssProcessor::process_style_with_options(&css, &options).unwrap_err();
+        let location = error.location().unwrap();
+        // Location is in literal source, after escapes (at the second dot, counted from 1).
+        assert_eq!(location.column as usize, literal.find("..").unwrap() + 2);
+
+        let tokens: proc_macro2::TokenStream = input.parse().unwrap();
+        let Some(proc_macro2::TokenTree::Group(group)) = tokens.into_iter().nth(2) else {
+            unreachable!()
+        };
+        // Literal is a single token, so error points to the whole literal.
+        let span =
+            super::span_at_location(input, group.stream(), START, location.line, location.column)
+                .unwrap();
+        assert_eq!(span.source_text().unwrap(), literal);
+    }
+
+    #[test]
+    fn check_error_span_after_interpolation() {
+        // Error is in the second `.x` selector, on the same line with interpolated value.
+        let input = "css! {\n    .x { width: ${\"1px\"}; } .x..x { }\n}";
+        let css = super::macro_input(input).unwrap();
+        let error = rcss_core::CssProcessor::process_style(&css).unwrap_err();
+        let location = error.location().unwrap();
+
+        let tokens: proc_macro2::TokenStream = input.parse().unwrap();
+        let Some(proc_macro2::TokenTree::Group(group)) = tokens.into_iter().nth(2) else {
+            unreachable!()
+        };
+        let span =
+            super::span_at_location(input, group.stream(), START, location.line, location.column)
+                .unwrap();
+        assert_eq!(span.source_text().unwrap(), ".");
+        assert_eq!(span.start().line, 2);
+        assert_eq!(span.start().column, 31);
+    }
 }
 
 /// Parses rcssAtRule from iterator of TokenTree.
 /// Expects that caller already take @ symbol.
-pub fn parse_rcss_config<I>(tokens: &mut Peekable<I>) -> Option<RcssAtRuleConfig>
+pub fn parse_rcss_config<I>(tokens: &mut Peekable<I>) -> Option<RcssAtRuleConfig<'static>>
 where
     I: Iterator<Item = TokenTree>,
 {
@@ -71,8 +269,47 @@ where
     None
 }
 
+/// Collect tokens of expressions (both runtime and constants) used in `${..}` interpolation.
+/// String literals are ignored, since they are placed into css as is.
+/// Order of expressions is the same as in `rcss_core::interpolate`.
+pub fn interpolated_expressions(tokens: TokenStream) -> Vec<TokenStream> {
+    let mut result = Vec::new();
+    let mut stack = vec![tokens.into_iter().peekable()];
+    while let Some(iter) = stack.last_mut() {
+        let Some(token) = iter.next() else {
+            stack.pop();
+            continue;
+        };
+        match token {
+            TokenTree::Punct(p) if p.as_char() == '$' => {
+                let Some(TokenTree::Group(group)) = iter.peek() else {
+                    continue;
+                };
+                if group.delimiter() != Delimiter::Brace {
+                    continue;
+                }
+                let stream = group.stream();
+                iter.next();
+                // Same as in rcss_core::interpolate, string literals are placed into css as is.
+                let is_literal = matches!(
+                    stream.clone().into_iter().next(),
+                    Some(TokenTree::Literal(l)) if l.to_string().starts_with('"')
+                );
+                if !is_literal {
+                    result.push(stream);
+                }
+            }
+            TokenTree::Group(group) => stack.push(group.stream().into_iter().peekable()),
+            _ => {}
+        }
+    }
+    result
+}
+
 pub trait CssOutputGenerateExt {
-    fn generate(&self) -> proc_macro2::TokenStream;
+    /// Generate struct for style.
+    /// If `warn_unused_classes` is set, fields of classes are checked by rustc `dead_code` lint.
+    fn generate(&self, warn_unused_classes: bool) -> proc_macro2::TokenStream;
 }
 
 #[derive(PartialEq, PartialOrd, Ord, Eq, Debug)]
@@ -95,42 +332,116 @@ impl Key {
     }
 }
 
-impl CssOutputGenerateExt for rcss_core::CssOutput {
-    fn generate(&self) -> proc_macro2::TokenStream {
-        #[allow(unused_mut)] //used in feature
-        let mut changed_classes = self.classes_map().clone();
-        #[cfg(feature = "auto-snake-case")]
-        {
-            use inflector::cases::snakecase::to_snake_case;
-            for (k, v) in self.classes_map() {
-                if !is_valid_rust_ident(k) {
-                    let mut snake_case = to_snake_case(k);
-                    if snake_case.chars().next().unwrap().is_numeric() {
-                        snake_case = format!("_{}", snake_case);
-                    }
-                    changed_classes.insert(snake_case, v.clone());
+/// Convert names of classes (or keyframes, custom properties) into valid rust field names.
+fn field_keys(classes: &BTreeMap<String, ClassInfo>) -> BTreeMap<Key, ClassInfo> {
+    #[allow(unused_mut)] //used in feature
+    let mut changed_classes = classes.clone();
+    #[cfg(feature = "auto-snake-case")]
+    {
+        use inflector::cases::snakecase::to_snake_case;
+        for (k, v) in classes {
+            if !is_valid_rust_ident(k) {
+                let mut snake_case = to_snake_case(k);
+                if snake_case.chars().next().unwrap().is_numeric() {
+                    snake_case = format!("_{}", snake_case);
                 }
+                changed_classes.insert(snake_case, v.clone());
             }
         }
+    }
 
-        let changed_classes: BTreeMap<_, _> = changed_classes
-            .into_iter()
-            .map(|(k, v)| {
-                if !is_valid_rust_ident(&k) {
-                    let new_key = format!("__kebab__{}", k.replace("-", "_k_"));
-                    assert!(is_valid_rust_ident(&new_key));
-                    (
-                        Key::Replaced {
-                            original: k,
-                            replaced: new_key,
-                        },
-                        v,
-                    )
-                } else {
-                    (Key::Valid(k), v)
-                }
+    changed_classes
+        .into_iter()
+        .map(|(k, v)| {
+            if !is_valid_rust_ident(&k) {
+                let new_key = kebab_field_name(&k);
+                (
+                    Key::Replaced {
+                        original: k,
+                        replaced: new_key,
+                    },
+                    v,
+                )
+            } else {
+                (Key::Valid(k), v)
+            }
+        })
+        .collect()
+}
+
+fn kebab_field_name(name: &str) -> String {
+    let field = format!("__kebab__{}", name.replace("-", "_k_"));
+    assert!(is_valid_rust_ident(&field));
+    field
+}
+
+/// Value of class field, class can be extended with classes from `composes:`.
+fn class_value(
+    class_name: &str,
+    composes: &BTreeMap<&str, &Vec<rcss_core::ComposedClass>>,
+) -> proc_macro2::TokenStream {
+    let Some(composed) = composes.get(class_name) else {
+        return quote::quote! { #class_name };
+    };
+    let composed = composed.iter().map(|c| match c {
+        rcss_core::ComposedClass::Local(class) | rcss_core::ComposedClass::Global(class) => {
+            quote::quote! { #class }
+        }
+        rcss_core::ComposedClass::External { class, path } => {
+            let field = if is_valid_rust_ident(class) {
+                quote::format_ident!("{}", class)
+            } else {
+                quote::format_ident!("{}", kebab_field_name(class))
+            };
+            quote::quote! { <#path>::new_root().#field }
+        }
+    });
+    quote::quote! {
+        ::rcss::reexport::const_format::concatcp!(#class_name, #(" ", #composed),*)
+    }
+}
+
+impl CssOutputGenerateExt for rcss_core::CssOutput {
+    fn generate(&self, warn_unused_classes: bool) -> proc_macro2::TokenStream {
+        let changed_classes = field_keys(self.classes_map());
+        let ids = field_keys(self.ids_map());
+        let keyframes = field_keys(self.keyframes_map());
+        // Composed classes by scoped name of class (since one class can be exposed through multiple fields).
+        let composes: BTreeMap<&str, &Vec<rcss_core::ComposedClass>> = self
+            .composes_map()
+            .iter()
+            .filter_map(|(class, composed)| {
+                let info = self.classes_map().get(class)?;
+                Some((info.class_name.as_str(), composed))
             })
             .collect();
+        // Custom properties are exposed without leading dashes (`--accent` => `css.vars.accent`).
+        let custom_properties = field_keys(
+            &self
+                .custom_properties_map()
+                .iter()
+                .map(|(k, v)| (k.trim_start_matches('-').to_owned(), v.clone()))
+                .collect(),
+        );
+        // Layers, containers, etc. are exposed through nested structs, like keyframes.
+        let scoped_names = [
+            (NameKind::Layer, "layers", "Layers"),
+            (NameKind::Container, "containers", "Containers"),
+            (NameKind::Counter, "counters", "Counters"),
+            (NameKind::GridArea, "areas", "Areas"),
+            (
+                NameKind::ViewTransition,
+                "view_transitions",
+                "ViewTransitions",
+            ),
+        ]
+        .map(|(kind, field, suffix)| {
+            let names = self
+                .scoped_names_map(kind)
+                .map(field_keys)
+                .unwrap_or_default();
+            (field, suffix, names)
+        });
 
         let struct_ident = self
             .declare()
@@ -146,9 +457,33 @@ impl CssOutputGenerateExt for rcss_core::CssOutput {
         });
 
         let uniq_class = self.class_name();
+        // Attribute scoping replaces scope class in selectors, so elements should have scope attribute instead.
+        let scope_attr = self
+            .scope_attr()
+            .map(|attr| quote::quote! { const SCOPE_ATTR: &'static str = #attr; });
+        let scope_consts = quote::quote! {
+            const SCOPE_ID: &'static str = #uniq_class;
+            #scope_attr
+        };
+
+        // Interpolated constants are concatenated with style at compile time.
+        let style = match concat_style_parts(self.style_parts()) {
+            Ok(style) => style,
+            Err(e) => return e.to_compile_error(),
+        };
 
-        let style: String = self.style_string();
+        let runtime_vars = match runtime_vars_format(self.runtime_variables()) {
+            Ok(vars) => vars,
+            Err(e) => return e.to_compile_error(),
+        };
 
+        // Fields of `pub` struct are part of its API and rustc doesn't report them as unused,
+        // so lint mode keeps them public and only checks structs with restricted visibility.
+        let warn_unused_classes = warn_unused_classes
+            && !matches!(
+                self.declare().map(|s| s.vis),
+                Some(syn::Visibility::Public(_))
+            );
         let vis_struct = self
             .declare()
             .map(|s| {
@@ -157,61 +492,261 @@ impl CssOutputGenerateExt for rcss_core::CssOutput {
                 quote::quote!(#vis #struct_)
             })
             .unwrap_or(quote::quote! { pub struct });
+        // Helper structs (for keyframes, ids, etc.) are placed in hidden module with visibility of style struct.
+        let names_mod_vis = self
+            .declare()
+            .map(|s| s.vis.to_token_stream())
+            .unwrap_or(quote::quote! { pub });
+        let names_mod = quote::format_ident!("__rcss_{}", struct_ident);
+        let names_mod_wrap = |items: proc_macro2::TokenStream| {
+            quote::quote! {
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                #names_mod_vis mod #names_mod {
+                    #items
+                }
+            }
+        };
+
+        // Helper structs are exposed through fields of style struct, so classes can't have the same names.
+        let nested_fields = [
+            ("ids", &ids),
+            ("keyframes", &keyframes),
+            ("vars", &custom_properties),
+        ]
+        .into_iter()
+        .chain(scoped_names.iter().map(|(field, _, names)| (*field, names)))
+        .filter(|(_, names)| !names.is_empty());
+        for (field, _) in nested_fields {
+            if let Some((key, info)) = changed_classes.iter().find(|(k, _)| k.field_str() == field)
+            {
+                let span = info.original_span.unwrap_or(proc_macro2::Span::call_site());
+                let msg = format!(
+                    "Class `{}` clashes with field `{field}` of style struct, that holds scoped names of style, \
+                    try to rename the class",
+                    key.original()
+                );
+                return syn::Error::new(span, msg).to_compile_error();
+            }
+        }
 
-        // TODO: find a way to warn on generated dead code (fields that wasn't accessed).
         let mut struct_impl = if let Some(extend) = self.extend() {
+            if runtime_vars.is_some() {
+                return quote::quote! {
+                    compile_error!("Runtime interpolation is not supported in styles that use @rcss(extend ..)")
+                };
+            }
+            if !custom_properties.is_empty() {
+                return quote::quote! {
+                    compile_error!("@rcss(scope-vars) is not supported in styles that use @rcss(extend ..)")
+                };
+            }
+            // Element has single id, so ids of parent and extension can't be merged like classes.
+            if !ids.is_empty() {
+                return quote::quote! {
+                    compile_error!("@rcss(scope-ids) is not supported in styles that use @rcss(extend ..)")
+                };
+            }
+            // Parent fields can't be accessed directly, since extension can contain classes
+            // that parent doesn't have, and it should be reported with clear error.
             let root_field_init = changed_classes.iter().map(|(k, v)| {
+                let name = k.field_str();
+                let v = class_value(&v.class_name, &composes);
+                quote::quote! {
+                    root = root.__rcss_set_class(#name, ::rcss::reexport::const_format::concatcp!(
+                        match ROOT.__rcss_class(#name) { Some(class) => class, None => "" },
+                        " ",
+                        #v
+                    ))
+                }
+            });
+            // Keyframes can't be merged, so keyframes from extension override parent ones.
+            let keyframes_init = keyframes.iter().map(|(k, v)| {
                 let span = v.original_span.unwrap_or(proc_macro2::Span::call_site());
                 let k = quote::format_ident!("{}", k.field_str(), span = span);
                 let v = Literal::string(&v.class_name);
                 quote::quote! {
-                    root.#k = ::rcss::reexport::const_format::concatcp!(ROOT.#k, " ", #v)
+                    root.keyframes.#k = #v
                 }
             });
-            generate_child_struct(
+            let names_init = scoped_names.iter().flat_map(|(field, _, names)| {
+                let field = quote::format_ident!("{}", field);
+                names.iter().map(move |(k, v)| {
+                    let span = v.original_span.unwrap_or(proc_macro2::Span::call_site());
+                    let k = quote::format_ident!("{}", k.field_str(), span = span);
+                    let v = Literal::string(&v.class_name);
+                    quote::quote! {
+                        root.#field.#k = #v
+                    }
+                })
+            });
+            let root_field_init = root_field_init.chain(keyframes_init).chain(names_init);
+            let new_classes = if self.extend_new_classes() {
+                // Classes that parent doesn't have are accessible through `classes()` method.
+                let ident = quote::format_ident!("{}Classes", struct_ident);
+                let names_struct = names_mod_wrap(generate_names_struct(
+                    quote::quote! { pub struct },
+                    &ident,
+                    &changed_classes,
+                ));
+                quote::quote! {
+                    #names_struct
+                    impl #struct_ident {
+                        /// Returns all classes of extension, including ones that parent style doesn't have.
+                        pub const fn classes(&self) -> #names_mod::#ident {
+                            #names_mod::#ident::new()
+                        }
+                    }
+                }
+            } else {
+                generate_new_classes_check(
+                    &struct_ident,
+                    &extend,
+                    &changed_classes,
+                    self.classes_map(),
+                )
+            };
+            let child_impl = generate_child_struct(
                 vis_struct,
                 &struct_ident,
                 extend,
                 &style,
-                &uniq_class,
+                &scope_consts,
                 root_field_init,
-            )
+            );
+            quote::quote! {
+                #child_impl
+                #new_classes
+            }
         } else {
             let field_init_struct = changed_classes.iter().map(|(k, v)| {
                 let span = v.original_span.unwrap_or(proc_macro2::Span::call_site());
                 let k = quote::format_ident!("{}", k.field_str(), span = span);
-                let v = Literal::string(&v.class_name);
+                let v = class_value(&v.class_name, &composes);
                 quote::quote! {
                     #k: #v
                 }
             });
-            let field_classes = changed_classes.iter().map(|(k, _v)| {
-                let field = quote::format_ident!("{}", k.field_str());
-                let pub_ = if matches!(k, Key::Valid(_)) {
-                    quote::quote! { pub }
-                } else {
-                    quote::quote! { #[doc(hidden)] pub}
+            // Public fields are never reported as dead code, so in lint mode they are crate visible.
+            let field_classes = changed_classes.iter().map(|(k, v)| {
+                // Lints are not reported in macro generated code,
+                // so in lint mode whole field should have span of the class.
+                let span = match warn_unused_classes {
+                    true => v.original_span.unwrap_or(proc_macro2::Span::call_site()),
+                    false => proc_macro2::Span::call_site(),
                 };
-                quote::quote! {
-                    #pub_ #field
+                let field = quote::format_ident!("{}", k.field_str(), span = span);
+                match (k, warn_unused_classes) {
+                    (Key::Valid(_), false) => quote::quote! { pub #field: &'static str },
+                    (Key::Replaced { .. }, false) => {
+                        quote::quote! { #[doc(hidden)] pub #field: &'static str }
+                    }
+                    (Key::Valid(_), true) => {
+                        quote::quote_spanned! {span=> pub(crate) #field: &'static str }
+                    }
+                    // Only one of the aliases is used, so don't report others.
+                    (Key::Replaced { .. }, true) => quote::quote_spanned! {span=>
+                        #[doc(hidden)] #[allow(dead_code)] pub(crate) #field: &'static str
+                    },
                 }
             });
-            generate_root_struct(
+            // Ids, keyframes, custom properties and other scoped names are exposed through nested structs.
+            let mut sub_structs = vec![];
+            let mut sub_structs_impl = vec![];
+            let names = [
+                ("ids", "Ids", &ids),
+                ("keyframes", "Keyframes", &keyframes),
+                ("vars", "Vars", &custom_properties),
+            ]
+            .into_iter()
+            .chain(
+                scoped_names
+                    .iter()
+                    .map(|(field, suffix, names)| (*field, *suffix, names)),
+            );
+            for (field, suffix, names) in names {
+                if names.is_empty() {
+                    continue;
+                }
+                let ident = quote::format_ident!("{}{}", struct_ident, suffix);
+                sub_structs_impl.push(generate_names_struct(
+                    quote::quote! { pub struct },
+                    &ident,
+                    names,
+                ));
+                sub_structs.push((
+                    quote::format_ident!("{}", field),
+                    quote::quote! { #names_mod::#ident },
+                ));
+            }
+            let root_impl = generate_root_struct(
                 vis_struct,
                 &struct_ident,
                 &style,
-                &uniq_class,
+                &scope_consts,
                 index_match_fields,
                 field_classes,
                 field_init_struct,
-            )
+                // Inline style can use local values, so they are evaluated at macro call site instead.
+                runtime_vars.as_ref().filter(|_| self.declare().is_some()),
+                &sub_structs,
+                // Index reads all fields, so it would hide unused ones.
+                !warn_unused_classes,
+            );
+            // Only declared structs can be extended.
+            let class_accessors = self.declare().map(|_| {
+                generate_class_accessors(&struct_ident, &changed_classes, warn_unused_classes)
+            });
+            let sub_structs_impl = (!sub_structs_impl.is_empty())
+                .then(|| names_mod_wrap(quote::quote! { #(#sub_structs_impl)* }));
+            quote::quote! {
+                #sub_structs_impl
+                #root_impl
+                #class_accessors
+            }
         };
+        // Mixins are exported as associated consts of declared struct.
+        if !self.mixins().is_empty() {
+            let mixins = match self
+                .mixins()
+                .iter()
+                .map(|(name, declarations)| {
+                    let name = quote::format_ident!("{}", name);
+                    let value = concat_style_parts(self.split_consts(declarations))?;
+                    Ok(quote::quote! {
+                        #[allow(non_upper_case_globals)]
+                        pub const #name: &'static str = #value;
+                    })
+                })
+                .collect::<syn::Result<Vec<_>>>()
+            {
+                Ok(mixins) => mixins,
+                Err(e) => return e.to_compile_error(),
+            };
+            struct_impl = quote::quote! {
+                #struct_impl
+                impl #struct_ident {
+                    #(#mixins)*
+                }
+            };
+        }
         // Convert to expression if it's not a declaration
         if self.declare().is_none() {
+            // Inline css can use local variables in interpolation,
+            // so runtime variables are evaluated at macro call site, and stored next to the style struct.
+            let init = if let Some(runtime_vars) = runtime_vars {
+                quote::quote! {
+                    ::rcss::WithVars::new(#struct_ident::new(), #runtime_vars)
+                }
+            } else {
+                quote::quote! {
+                    #struct_ident::new()
+                }
+            };
             struct_impl = quote::quote! {
                 {
                 #struct_impl
-                   #struct_ident::new()
+                   #init
                 }
             };
         };
@@ -220,16 +755,81 @@ impl CssOutputGenerateExt for rcss_core::CssOutput {
     }
 }
 
+/// Build const expression from parts of style, constants are concatenated with literals at compile time.
+fn concat_style_parts(
+    parts: Vec<rcss_core::StylePart<'_>>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let literals = parts
+        .iter()
+        .map(|part| match part {
+            rcss_core::StylePart::Literal(s) => Some(*s),
+            rcss_core::StylePart::Const(_) => None,
+        })
+        .collect::<Option<String>>();
+    if let Some(style) = literals {
+        return Ok(quote::quote! { #style });
+    }
+    let parts = parts
+        .into_iter()
+        .map(|part| match part {
+            rcss_core::StylePart::Literal(s) => Ok(quote::quote! { #s }),
+            rcss_core::StylePart::Const(var) => {
+                let path = match &var.original_tokens {
+                    Some(tokens) => syn::parse2::<syn::Path>(tokens.clone())?,
+                    None => syn::parse_str::<syn::Path>(&var.path)?,
+                };
+                Ok(quote::quote! { #path })
+            }
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    Ok(quote::quote! { ::rcss::reexport::const_format::concatcp!(#(#parts),*) })
+}
+
+/// Build expression that renders runtime variables into inline style (like `--v0-Mlfe:red`).
+/// Returns None if style has no runtime interpolation.
+fn runtime_vars_format(
+    vars: &[rcss_core::RuntimeVariable],
+) -> syn::Result<Option<proc_macro2::TokenStream>> {
+    if vars.is_empty() {
+        return Ok(None);
+    }
+    let format_str = vars
+        .iter()
+        .map(|v| format!("{}:{{}}", v.name))
+        .collect::<Vec<_>>()
+        .join(";");
+    let exprs = vars
+        .iter()
+        .map(|v| match &v.original_tokens {
+            Some(tokens) => syn::parse2::<syn::Expr>(tokens.clone()),
+            None => syn::parse_str::<syn::Expr>(&v.expr),
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    Ok(Some(quote::quote! {
+        ::std::format!(#format_str, #((#exprs)),*)
+    }))
+}
+
+#[allow(clippy::too_many_arguments)]
 fn generate_root_struct(
     vis_struct: proc_macro2::TokenStream,
     struct_ident: &proc_macro2::Ident,
-    style: &str,
-    uniq_class: &str,
+    style: &proc_macro2::TokenStream,
+    scope_consts: &proc_macro2::TokenStream,
     index_match_fields: impl Iterator<Item = proc_macro2::TokenStream>,
     field_classes: impl Iterator<Item = proc_macro2::TokenStream>,
     field_init: impl Iterator<Item = proc_macro2::TokenStream>,
+    runtime_vars: Option<&proc_macro2::TokenStream>,
+    sub_structs: &[(proc_macro2::Ident, proc_macro2::TokenStream)],
+    generate_index: bool,
 ) -> proc_macro2::TokenStream {
-    let index_impl = if cfg!(feature = "indexed-classes") {
+    let sub_structs_field = sub_structs
+        .iter()
+        .map(|(field, ty)| quote::quote! { pub #field: #ty, });
+    let sub_structs_init = sub_structs
+        .iter()
+        .map(|(field, ty)| quote::quote! { #field: #ty::new(), });
+    let index_impl = if cfg!(feature = "indexed-classes") && generate_index {
         quote::quote! {
             impl<'a> std::ops::Index<&'a str> for #struct_ident {
                 type Output = str;
@@ -245,20 +845,34 @@ fn generate_root_struct(
         quote::quote! {}
     };
 
+    // Runtime values are not stored in struct, so it stays Copy.
+    // Expressions of declared struct can't use local values, so they are evaluated on each call.
+    let vars_method = runtime_vars.map(|runtime_vars| {
+        quote::quote! {
+            /// Values of runtime interpolated expressions,
+            /// should be set as inline `style` attribute on element with scoped class.
+            pub fn vars_style(&self) -> ::std::string::String {
+                #runtime_vars
+            }
+        }
+    });
+
     quote::quote! {
         // allow non_snake_case for `__kebab__baz_k_2` style fields
         #[allow(non_snake_case)]
         #[must_use = "Scope style should be registered"]
         #[derive(Debug, Copy, Clone)]
         #vis_struct #struct_ident {
-            #(#field_classes: &'static str),*
+            #(#sub_structs_field)*
+            #(#field_classes),*
         }
         impl #struct_ident {
-
             pub fn new() -> Self {
                 Self::new_root()
             }
 
+            #vars_method
+
             /// Const fn is not stabilized in traits, so we use it in structure.
             /// Methods new_root is used to create constant time root object, and modify thier content.
             /// It allow us to have resulted css object on compile time.
@@ -267,6 +881,7 @@ fn generate_root_struct(
             /// TODO: Later when const trait will be stabilized we can move it into ScopeChain trait.
             pub const fn new_root() -> Self {
                 Self {
+                    #(#sub_structs_init)*
                     #(#field_init),*
                 }
             }
@@ -283,7 +898,7 @@ fn generate_root_struct(
         #[must_use = "Scope style should be registered"]
         impl ::rcss::ScopeCommon for #struct_ident {
             const STYLE: &'static str = #style;
-            const SCOPE_ID: &'static str = #uniq_class;
+            #scope_consts
         }
 
         impl ::rcss::extend::ScopeChain for #struct_ident {
@@ -302,12 +917,120 @@ fn generate_root_struct(
     }
 }
 
+/// Access to classes of root style by name, used by extensions.
+///
+/// Reading fields marks them as used, so in lint mode accessors are crate visible,
+/// and classes are reported only if style is never extended.
+fn generate_class_accessors(
+    struct_ident: &proc_macro2::Ident,
+    classes: &BTreeMap<Key, ClassInfo>,
+    warn_unused_classes: bool,
+) -> proc_macro2::TokenStream {
+    let vis = match warn_unused_classes {
+        true => quote::quote! { pub(crate) },
+        false => quote::quote! { pub },
+    };
+    let names = classes.keys().map(|k| k.field_str()).collect::<Vec<_>>();
+    let fields = classes
+        .keys()
+        .map(|k| quote::format_ident!("{}", k.field_str()))
+        .collect::<Vec<_>>();
+    quote::quote! {
+        impl #struct_ident {
+            #[doc(hidden)]
+            #vis const fn __rcss_class(&self, name: &str) -> Option<&'static str> {
+                #(if ::rcss::extend::str_eq(name, #names) { return Some(self.#fields); })*
+                None
+            }
+            #[doc(hidden)]
+            #vis const fn __rcss_set_class(mut self, name: &str, class: &'static str) -> Self {
+                #(if ::rcss::extend::str_eq(name, #names) { self.#fields = class; })*
+                self
+            }
+        }
+    }
+}
+
+/// Compile time check that extension doesn't add classes, that parent style doesn't have.
+/// Error is reported at the class in style.
+fn generate_new_classes_check(
+    struct_ident: &proc_macro2::Ident,
+    path_to_parent: &syn::Path,
+    classes: &BTreeMap<Key, ClassInfo>,
+    original_classes: &BTreeMap<String, ClassInfo>,
+) -> proc_macro2::TokenStream {
+    let parent = quote::ToTokens::to_token_stream(path_to_parent)
+        .to_string()
+        .replace(' ', "");
+    // Skip snake case aliases, to report class with the name used in style.
+    let classes = classes
+        .iter()
+        .filter(|(k, _)| original_classes.contains_key(k.original()));
+    let checks = classes.map(|(k, v)| {
+        let span = v.original_span.unwrap_or(proc_macro2::Span::call_site());
+        let name = k.field_str();
+        let msg = format!(
+            "Class `{}` is not declared in `{parent}`, extension can't add new classes. \
+            Use `@rcss(extend {parent} with new-classes)` to allow it.",
+            k.original()
+        );
+        quote::quote_spanned! {span=>
+            if ROOT.__rcss_class(#name).is_none() {
+                panic!(#msg)
+            }
+        }
+    });
+    quote::quote! {
+        const _: () = {
+            const ROOT: <#struct_ident as ::rcss::extend::ScopeChain>::Root =
+                <#struct_ident as ::rcss::extend::ScopeChain>::Root::new_root();
+            #(#checks)*
+        };
+    }
+}
+
+/// Struct with scoped names of `@keyframes`, custom properties or layers, containers, etc. (like `css.keyframes.spin`).
+fn generate_names_struct(
+    vis_struct: proc_macro2::TokenStream,
+    names_ident: &proc_macro2::Ident,
+    names: &BTreeMap<Key, ClassInfo>,
+) -> proc_macro2::TokenStream {
+    let fields = names.keys().map(|k| {
+        let field = quote::format_ident!("{}", k.field_str());
+        if matches!(k, Key::Valid(_)) {
+            quote::quote! { pub #field }
+        } else {
+            quote::quote! { #[doc(hidden)] pub #field }
+        }
+    });
+    let fields_init = names.iter().map(|(k, v)| {
+        let span = v.original_span.unwrap_or(proc_macro2::Span::call_site());
+        let k = quote::format_ident!("{}", k.field_str(), span = span);
+        let v = Literal::string(&v.class_name);
+        quote::quote! { #k: #v }
+    });
+    quote::quote! {
+        #[allow(non_snake_case)]
+        #[derive(Debug, Copy, Clone)]
+        #vis_struct #names_ident {
+            #(#fields: &'static str),*
+        }
+        impl #names_ident {
+            pub const fn new() -> Self {
+                Self {
+                    #(#fields_init),*
+                }
+            }
+        }
+    }
+}
+
 fn generate_child_struct(
     vis_struct: proc_macro2::TokenStream,
     struct_ident: &proc_macro2::Ident,
     path_to_parent: syn::Path,
-    style: &str,
-    uniq_class: &str,
+    style: &proc_macro2::TokenStream,
+    scope_consts: &proc_macro2::TokenStream,
     field_init: impl Iterator<Item = proc_macro2::TokenStream>,
 ) -> proc_macro2::TokenStream {
     quote::quote! {
@@ -359,7 +1082,7 @@ fn generate_child_struct(
 
         impl ::rcss::ScopeCommon for #struct_ident {
             const STYLE: &'static str = #style;
-            const SCOPE_ID: &'static str = #uniq_class;
+            #scope_consts
         }
 
 
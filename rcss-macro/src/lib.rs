@@ -4,6 +4,7 @@ use proc_macro2::Span;
 use quote::quote_spanned;
 use rcss_core::CssOutput;
 
+mod config;
 mod fallback_ide;
 mod helpers;
 use helpers::CssOutputGenerateExt;
@@ -12,12 +13,58 @@ use helpers::CssOutputGenerateExt;
 /// Don't use this macro directly, use rcss crate instead, since bundler will ignore macro that used directly.
 #[proc_macro]
 pub fn css(tokens: TokenStream) -> TokenStream {
-    let output = if Span::call_site().source_text().is_some() {
-        match css_inner() {
-            Ok(output) => output,
+    let source_text = Span::call_site().source_text();
+    let text = match &source_text {
+        Some(source_text) => helpers::macro_input(source_text),
+        // Style in string literal is available even without source text (like in IDE).
+        None => syn::parse::<syn::LitStr>(tokens.clone())
+            .ok()
+            .map(|literal| literal.value()),
+    };
+    let output = if source_text.is_some() || text.is_some() {
+        // Errors in string literal are reported relative to its source, since value can have escapes.
+        let literal = source_text.as_deref().and_then(helpers::macro_literal);
+        match css_inner(text, literal) {
+            Ok(mut output) => {
+                // Use original tokens of interpolated expressions, to keep their spans.
+                let (consts, expressions): (Vec<_>, Vec<_>) =
+                    helpers::interpolated_expressions(tokens.clone().into())
+                        .into_iter()
+                        .partition(|tokens| {
+                            rcss_core::interpolate::is_const_path(&tokens.to_string())
+                        });
+                for (var, tokens) in output.runtime_variables_mut().iter_mut().zip(expressions) {
+                    var.original_tokens = Some(tokens);
+                }
+                for (var, tokens) in output.const_variables_mut().iter_mut().zip(consts) {
+                    var.original_tokens = Some(tokens);
+                }
+                // Unused fields and new classes in extension are reported at span of their class.
+                if config::warn_unused_classes() || output.extend().is_some() {
+                    let spans = fallback_ide::class_spans(tokens.into());
+                    for (class, info) in output.classes_map_mut() {
+                        info.original_span = spans.get(class).copied();
+                    }
+                }
+                output
+            }
             Err(e) => {
                 let msg = format!("{}", e);
-                return quote_spanned! {Span::call_site()=>
+                // Point to the token that caused error, if css parser provide its location.
+                let span = e
+                    .location()
+                    .zip(source_text)
+                    .and_then(|(loc, source_text)| {
+                        helpers::span_at_location(
+                            &source_text,
+                            tokens.into(),
+                            Span::call_site().start(),
+                            loc.line,
+                            loc.column,
+                        )
+                    })
+                    .unwrap_or(Span::call_site());
+                return quote_spanned! {span=>
                     compile_error!(#msg)
                 }
                 .into();
@@ -27,7 +74,14 @@ pub fn css(tokens: TokenStream) -> TokenStream {
         fallback_ide::parse(tokens.into())
     };
 
-    output.generate().into()
+    let generated = output.generate(config::warn_unused_classes());
+    let manifest = config::manifest_dependency();
+    // Inline style is an expression, so manifest dependency is placed into block with it.
+    match output.declare() {
+        Some(_) => quote::quote! { #manifest #generated },
+        None => quote::quote! { { #manifest #generated } },
+    }
+    .into()
 }
 #[derive(thiserror::Error, Debug)]
 enum MacroError {
@@ -35,18 +89,40 @@ enum MacroError {
     ParseError(#[from] rcss_core::Error),
     #[error("No valid source code available for this macro call.")]
     NoSourceAvailable,
+    #[error("Invalid rcss config in Cargo.toml: {0}")]
+    InvalidConfig(String),
+}
+impl MacroError {
+    fn location(&self) -> Option<rcss_core::ErrorLocation> {
+        match self {
+            MacroError::ParseError(e) => e.location(),
+            MacroError::NoSourceAvailable | MacroError::InvalidConfig(_) => None,
+        }
+    }
 }
 
-/// Return None if macro input is invalid.
-/// Or if source_text is not found.
-fn css_inner() -> Result<CssOutput, MacroError> {
-    let Some(text) = Span::call_site().source_text() else {
+/// Process macro input, returned by `helpers::macro_input`.
+/// `literal` is source text of string literal, if style is its value.
+/// Return error if macro input was not found in source text.
+fn css_inner(text: Option<String>, literal: Option<&str>) -> Result<CssOutput, MacroError> {
+    let Some(text) = text else {
         return Err(MacroError::NoSourceAvailable);
     };
-    let Some(text) = helpers::macro_input(&text) else {
-        return Err(MacroError::NoSourceAvailable);
+    // Browser targets are configured by root crate metadata, and passed from build.rs.
+    let browsers = match option_env!("RCSS_TARGETS") {
+        Some(query) => rcss_core::browsers_from_query(query)?,
+        None => None,
     };
-    let mut output = rcss_core::CssProcessor::process_style(&text)?;
+    let naming = config::naming().map_err(MacroError::InvalidConfig)?;
+    let scoping = config::scoping().map_err(MacroError::InvalidConfig)?;
+    let mut options = rcss_core::CssProcessorOptions::new()
+        .targets(browsers)
+        .naming(naming)
+        .scoping(scoping);
+    if let Some(literal) = literal {
+        options = options.literal(literal);
+    }
+    let mut output = rcss_core::CssProcessor::process_style_with_options(&text, &options)?;
     if cfg!(disable_styles) {
         output.clear_styles();
         // panic!("Styles was disabled.")
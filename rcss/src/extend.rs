@@ -28,6 +28,24 @@ pub trait ScopeChain: Sized {
     fn into_root(self) -> <Self as crate::extend::ScopeChain>::Root;
 }
 
+/// Compare strings in const context.
+/// Used by generated code to access classes of root style by name.
+#[doc(hidden)]
+pub const fn str_eq(left: &str, right: &str) -> bool {
+    let (left, right) = (left.as_bytes(), right.as_bytes());
+    if left.len() != right.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < left.len() {
+        if left[i] != right[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
 pub use in_chain_ops::ScopeChainOps;
 /// Hack type that is used instead of generic Into<T>.
 /// Used to avoid conflicts with type_builder default value for optional generic.
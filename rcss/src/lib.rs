@@ -38,8 +38,8 @@
 /// let html = format!(r#"<div class="{}">Hello</div>"#, css.my_class);
 /// // Note: This style is only used for current style, and doesn't combine well with extend.
 /// let html = format!("<style>{}</style>\n{}", css.scope_style(), html);
-/// assert_eq!(html, "<style>.my-class-Mlfe{color:red}</style>\n\
-/// <div class=\"my-class-Mlfe\">Hello</div>");
+/// assert_eq!(html, "<style>.my-class-StkI{color:red}</style>\n\
+/// <div class=\"my-class-StkI\">Hello</div>");
 /// ```
 pub use rcss_macro::css;
 /// Common types that used in defining scopes for css.
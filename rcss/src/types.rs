@@ -9,10 +9,79 @@ pub trait ScopeCommon {
     /// It should contain valid css style.
     /// Note: It can be empty, if any root crate uses rcss-bundler and sets `metadata.rcss.disable-styles = true`.
     const STYLE: &'static str;
+    /// Attribute that scopes style, if crate uses attribute scoping (`metadata.rcss.scoping = "attribute"`).
+    /// Each element of component should have this attribute (with any value), since selectors use `[data-rcss-XXXX]`
+    /// instead of scope class.
+    /// Empty, if style is scoped with class.
+    const SCOPE_ATTR: &'static str = "";
 
     fn scope_style(&self) -> &'static str {
         Self::STYLE
     }
+
+    /// Returns name of attribute, that framework integrations should set on each element of component,
+    /// or None, if style is scoped with class.
+    fn scope_attr(&self) -> Option<&'static str> {
+        (!Self::SCOPE_ATTR.is_empty()).then_some(Self::SCOPE_ATTR)
+    }
+}
+
+/// Style that uses local values in runtime interpolation (`${expr}`).
+///
+/// Returned by inline `css!` macro instead of style struct, since values are evaluated at macro call site.
+/// Dereferences to style struct, which is still `Copy`, values are rendered by [`WithVars::vars_style`].
+#[derive(Debug, Clone)]
+pub struct WithVars<S> {
+    scope: S,
+    vars: String,
+}
+
+impl<S> WithVars<S> {
+    #[doc(hidden)]
+    pub fn new(scope: S, vars: String) -> Self {
+        Self { scope, vars }
+    }
+
+    /// Values of runtime interpolated expressions,
+    /// should be set as inline `style` attribute on element with scoped class.
+    pub fn vars_style(&self) -> &str {
+        &self.vars
+    }
+
+    /// Returns style struct without runtime values.
+    pub fn scope(&self) -> S
+    where
+        S: Copy,
+    {
+        self.scope
+    }
+}
+
+impl<S> std::ops::Deref for WithVars<S> {
+    type Target = S;
+    fn deref(&self) -> &Self::Target {
+        &self.scope
+    }
+}
+
+impl<S: ScopeCommon> ScopeCommon for WithVars<S> {
+    const SCOPE_ID: &'static str = S::SCOPE_ID;
+    const STYLE: &'static str = S::STYLE;
+    const SCOPE_ATTR: &'static str = S::SCOPE_ATTR;
+}
+
+impl<S> crate::extend::ScopeChain for WithVars<S>
+where
+    S: crate::extend::ScopeChain<Parent = std::convert::Infallible, Root = S>,
+{
+    type Parent = std::convert::Infallible;
+    type Root = S;
+    fn from_root(root: S) -> Self {
+        Self::new(root, String::new())
+    }
+    fn into_root(self) -> S {
+        self.scope
+    }
 }
 
 #[cfg(test)]
@@ -210,6 +279,18 @@ mod static_test {
         assert_eq!(dyn_style.root_scope_id(), "UNIQ_ID");
     }
 
+    #[test]
+    fn scope_attr() {
+        struct Attributed;
+        impl ScopeCommon for Attributed {
+            const STYLE: &'static str = "div[data-rcss-abcd]{}";
+            const SCOPE_ID: &'static str = "UNIQ_ID";
+            const SCOPE_ATTR: &'static str = "data-rcss-abcd";
+        }
+        assert_eq!(Attributed.scope_attr(), Some("data-rcss-abcd"));
+        assert_eq!(DeepExtension::new().scope_attr(), None);
+    }
+
     #[test]
     fn get_all_scopes() {
         use crate::extend::in_chain_ops::ScopeChainOps;
@@ -20,3 +20,291 @@ fn test() {
     let foo = Bar::default();
     let _ = foo.my_class;
 }
+
+#[test]
+fn runtime_interpolation() {
+    let color = "blue";
+    let padding = 2;
+    let css = rcss::css! {
+        .card {
+            color: ${color};
+            padding: ${"1em"} ${format!("{}px", padding * 2)};
+        }
+    };
+    let suffix = css.card.rsplit('-').next().unwrap();
+    assert_eq!(
+        css.vars_style(),
+        format!("--v0-{suffix}:blue;--v1-{suffix}:4px")
+    );
+    // Runtime values are stored outside of style struct, so it is still Copy.
+    assert_copy(&css.scope());
+}
+
+#[cfg(test)]
+fn assert_copy<T: Copy>(_: &T) {}
+
+fn accent() -> &'static str {
+    "orange"
+}
+
+rcss::css! {
+    @rcss(pub struct Themed);
+
+    .title {
+        color: ${accent()};
+    }
+}
+
+#[test]
+fn runtime_interpolation_in_declared_struct() {
+    let css = Themed::new();
+    let suffix = css.title.rsplit('-').next().unwrap();
+    assert_eq!(css.vars_style(), format!("--v0-{suffix}:orange"));
+    assert_copy(&css);
+}
+
+mod theme {
+    pub const PRIMARY: &str = "green";
+    pub const GAP: u32 = 4;
+}
+
+rcss::css! {
+    @rcss(pub struct Tokens);
+
+    .button {
+        color: ${crate::theme::PRIMARY};
+        margin: ${theme::GAP}px;
+    }
+}
+
+#[test]
+fn const_interpolation() {
+    use rcss::ScopeCommon;
+    let button = Tokens::new().button;
+    assert_eq!(
+        Tokens::STYLE,
+        format!(".{button}{{color:green;margin:4px}}")
+    );
+}
+
+rcss::css! {
+    @rcss(pub struct Mixins);
+
+    @rcss(mixin focus_ring) {
+        outline: 2px solid ${crate::theme::PRIMARY};
+        outline-offset: 2px;
+    }
+}
+
+rcss::css! {
+    @rcss(pub struct Input);
+
+    .input {
+        @include crate::Mixins::focus_ring;
+        color: red;
+    }
+}
+
+#[test]
+fn mixins() {
+    use rcss::ScopeCommon;
+    let input = Input::new().input;
+    assert_eq!(
+        Mixins::focus_ring,
+        "outline:2px solid green;outline-offset:2px"
+    );
+    assert_eq!(Mixins::STYLE, "");
+    assert_eq!(
+        Input::STYLE,
+        format!(".{input}{{outline:2px solid green;outline-offset:2px;color:red}}")
+    );
+}
+
+rcss::css! {
+    @rcss(pub struct Spinner);
+
+    .spinner {
+        animation: spin 1s linear infinite;
+    }
+    @keyframes spin {
+        from { transform: rotate(0deg); }
+        to { transform: rotate(360deg); }
+    }
+}
+
+rcss::css! {
+    @rcss(extend Spinner);
+    @rcss(pub struct SlowSpinner);
+
+    .spinner {
+        animation: spin 3s linear infinite;
+    }
+    @keyframes spin {
+        to { transform: rotate(-360deg); }
+    }
+}
+
+#[test]
+fn keyframes_scoping() {
+    use rcss::ScopeCommon;
+    let css = Spinner::new();
+    assert!(css.keyframes.spin.starts_with("spin-"));
+    assert!(Spinner::STYLE.contains(&format!("@keyframes {}", css.keyframes.spin)));
+
+    let slow = SlowSpinner::new();
+    assert_ne!(slow.keyframes.spin, css.keyframes.spin);
+    assert!(SlowSpinner::STYLE.contains(&format!("@keyframes {}", slow.keyframes.spin)));
+}
+
+rcss::css! {
+    @rcss(pub struct Card);
+    @rcss(scope-vars);
+
+    .card {
+        --accent: red;
+        color: var(--accent);
+    }
+}
+
+#[test]
+fn scope_vars() {
+    use rcss::ScopeCommon;
+    let css = Card::new();
+    let suffix = css.card.rsplit('-').next().unwrap();
+    assert_eq!(css.vars.accent, format!("--accent-{suffix}"));
+    assert_eq!(
+        Card::STYLE,
+        format!(
+            ".{}{{{accent}:red;color:var({accent})}}",
+            css.card,
+            accent = css.vars.accent
+        )
+    );
+}
+
+rcss::css! {
+    @rcss(pub struct Layout);
+
+    @layer base {
+        .page {
+            container-name: page;
+            grid-template-areas: "header" "main";
+        }
+    }
+    .title {
+        grid-area: header;
+        view-transition-name: title;
+    }
+    @container page (min-width: 600px) {
+        .title { color: red; }
+    }
+}
+
+#[test]
+fn scoped_names() {
+    use rcss::ScopeCommon;
+    let css = Layout::new();
+    let suffix = css.page.rsplit('-').next().unwrap();
+    assert_eq!(css.layers.base, format!("base-{suffix}"));
+    assert_eq!(css.containers.page, format!("page-{suffix}"));
+    assert_eq!(css.areas.header, format!("header-{suffix}"));
+    assert_eq!(css.view_transitions.title, format!("title-{suffix}"));
+    assert!(Layout::STYLE.contains(&format!("@layer {}", css.layers.base)));
+    assert!(Layout::STYLE.contains(&format!("@container {} (", css.containers.page)));
+    assert!(Layout::STYLE.contains(&format!("grid-area:{}", css.areas.header)));
+}
+
+rcss::css! {
+    @rcss(pub struct Dialog);
+    @rcss(scope-ids);
+
+    #dialog {
+        color: red;
+    }
+    #dialog-title {
+        font-weight: bold;
+    }
+}
+
+#[test]
+fn scope_ids() {
+    use rcss::ScopeCommon;
+    let css = Dialog::new();
+    assert!(css.ids.dialog.starts_with("dialog-"));
+    assert!(css.ids.dialog_title.starts_with("dialog-title-"));
+    assert!(Dialog::STYLE.contains(&format!("#{}{{color:red}}", css.ids.dialog)));
+}
+
+rcss::css!(
+    r#"
+    @rcss(pub struct Quote);
+
+    .quote::before {
+        content: '{';
+        margin: 3em;
+        color: #0ed;
+    }
+    "#
+);
+
+#[test]
+fn string_literal_input() {
+    use rcss::ScopeCommon;
+    let css = Quote::new();
+    assert!(css.quote.starts_with("quote-"));
+    let expected = format!(
+        ".{}:before{{content:\"{{\";margin:3em;color:#0ed}}",
+        css.quote
+    );
+    assert_eq!(Quote::STYLE, expected);
+}
+
+rcss::css! {
+    @rcss(pub struct Buttons);
+
+    .base {
+        padding: 4px;
+    }
+    .primary {
+        composes: base;
+        composes: card from crate::Card;
+        composes: btn from global;
+        color: blue;
+    }
+}
+
+#[test]
+fn composes() {
+    use rcss::ScopeCommon;
+    let css = Buttons::new();
+    let card = Card::new().card;
+    let primary = Buttons::STYLE
+        .split(['.', '{'])
+        .find(|s| s.starts_with("primary-"))
+        .unwrap();
+    assert_eq!(css.primary, format!("{primary} {} {card} btn", css.base));
+    assert!(!Buttons::STYLE.contains("composes"));
+}
+
+rcss::css! {
+    @rcss(extend Foo with new-classes);
+    @rcss(pub struct FooWithHook);
+
+    .my_class {
+        color: blue;
+    }
+    .hook {
+        color: green;
+    }
+}
+
+#[test]
+fn extend_with_new_classes() {
+    let foo = Foo::new();
+    let css = FooWithHook::new();
+    assert!(css
+        .my_class
+        .starts_with(&format!("{} my_class-", foo.my_class)));
+    assert!(css.classes().hook.starts_with("hook-"));
+    assert!(css.classes().my_class.starts_with("my_class-"));
+}